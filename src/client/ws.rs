@@ -1,6 +1,11 @@
+use crate::components::game::context::LocalState;
+use crate::components::pages::room_exists;
 use crate::extensions::ResultExt;
 use crate::typed_context::*;
-use crate::types::{ClientGameState, ClientMessage, PlayerId, ServerMessage, TimerTag};
+use crate::types::{
+    ClientGameState, ClientMessage, ErrorCode, GameStep, PlayerId, ServerMessage, StatePatch,
+    TimerSecs, TimerTag,
+};
 use ::futures::{stream::SplitSink, SinkExt, StreamExt};
 use ::gloo_net::websocket::{futures::WebSocket, Message};
 use ::gloo_timers::future::sleep;
@@ -11,15 +16,25 @@ use ::std::time::Duration;
 
 define_context!(WS_Writer, RwSignal<Option<SplitSink<WebSocket, Message>>>);
 
+/// Longest wait between reconnect attempts, in seconds.
+/// Short enough for a few attempts within the shortest reconnect grace period.
+const MAX_BACKOFF: u64 = TimerSecs::MIN / 2;
+
 ///
 /// # Panics
 /// - May panic if the page url cannot be obtained from the browser
-pub fn connect_to_server(game_state: RwSignal<ClientGameState>, player_id: RwSignal<PlayerId>) {
+pub fn connect_to_server(
+    game_state: RwSignal<ClientGameState>,
+    local_state: RwSignal<LocalState>,
+    player_id: RwSignal<PlayerId>,
+    room_code: &str,
+) {
     let loc = leptos::window().location();
     let host = loc.host().unwrap();
     let protocol = loc.protocol().unwrap();
     let protocol = if protocol == "https:" { "wss:" } else { "ws:" };
-    let uri = format!("{protocol}//{host}/ws");
+    let uri = format!("{protocol}//{host}/ws/{room_code}");
+    let room_code = room_code.to_owned();
 
     let signal_ws_writer = create_rw_signal(None);
     provide_typed_context::<WS_Writer>(signal_ws_writer);
@@ -30,17 +45,52 @@ pub fn connect_to_server(game_state: RwSignal<ClientGameState>, player_id: RwSig
             let (writer, mut reader) = WebSocket::open(&uri).unwrap().split();
             signal_ws_writer.set(Some(writer));
 
+            let mut refused = false;
             while let Some(msg) = reader.next().await {
                 if let Some(Message::Text(m)) = msg.ok_or_log() {
+                    // connected, so the next drop starts over with a short wait
+                    backoff = 1;
                     if let Some(m) = serde_json::from_str(&m).ok_or_log() {
-                        game_state.update(|g| apply_server_message(g, m));
+                        refused |= matches!(
+                            m,
+                            ServerMessage::Error {
+                                code: ErrorCode::DuplicateSession,
+                                ..
+                            }
+                        );
+                        let mut in_sync = true;
+                        // effects see both signals change together
+                        leptos::batch(|| {
+                            game_state.update(|g| {
+                                local_state.update(|l| in_sync = apply_server_message(g, l, m));
+                            });
+                        });
+                        if !in_sync {
+                            send(signal_ws_writer, ClientMessage::Resync).await;
+                        }
                     }
                 }
             }
             signal_ws_writer.set(None);
             log!("disconnected");
+
+            // reconnecting would just be refused again
+            if refused {
+                break;
+            }
+            // an error means the server is unreachable, e.g. restarting, so keep trying
+            if let Ok(false) = room_exists(room_code.clone()).await {
+                local_state
+                    .update(|l| l.toast("The game is over, since the room was closed".to_owned()));
+                break;
+            }
+            local_state.update(|l| {
+                l.toast(format!(
+                    "Lost connection to the server. Reconnecting in {backoff} seconds..."
+                ));
+            });
             sleep(Duration::new(backoff, 0)).await;
-            backoff *= 2;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
         }
     });
 
@@ -93,14 +143,66 @@ fn serialize(message: &ClientMessage) -> Message {
     Message::Text(serde_json::to_string(message).expect("ClientMessage serialization failed"))
 }
 
-fn apply_server_message(state: &mut ClientGameState, message: ServerMessage) {
+/// Returns false if a patch was missed, and the client needs to resync.
+fn apply_server_message(
+    state: &mut ClientGameState,
+    local: &mut LocalState,
+    message: ServerMessage,
+) -> bool {
     match message {
         ServerMessage::GameState(g) => {
-            // replace the current game state completely
+            local.synced_config.clone_from(&g.config);
             *state = g;
         }
 
-        ServerMessage::PlayerJoined(new) => {
+        ServerMessage::Patch { version, patch } => {
+            // already included in the last full game state
+            if version <= state.version {
+                return true;
+            }
+            if version != state.version + 1 {
+                log!("missed patch {}, resyncing", state.version + 1);
+                return false;
+            }
+            state.version = version;
+            apply_patch(state, local, patch);
+        }
+
+        ServerMessage::UpdateRemainingTime(time) => {
+            state.timer = time;
+        }
+
+        ServerMessage::Error {
+            code,
+            message,
+            in_reply_to,
+        } => {
+            log!(
+                "{:?} error in reply to {:?}: {}",
+                code,
+                in_reply_to,
+                message
+            );
+            local.toast(message);
+        }
+
+        ServerMessage::Hint(hint) => {
+            local.hint = Some(hint);
+        }
+
+        ServerMessage::OwnSubmission(token) => {
+            state.own_submission = Some(token);
+        }
+    }
+    true
+}
+
+/// Mirrors what `GameState::to_client_state` would produce on the server, after the same change.
+#[allow(clippy::too_many_lines)]
+fn apply_patch(state: &mut ClientGameState, local: &mut LocalState, patch: StatePatch) {
+    match patch {
+        StatePatch::PlayerJoined(new) => {
+            state.audience.retain(|p| p.id != new.id);
             if let Some(p) = state.players.iter_mut().find(|p| p.id == new.id) {
                 p.name = new.name;
             } else {
@@ -108,19 +210,124 @@ fn apply_server_message(state: &mut ClientGameState, message: ServerMessage) {
             }
         }
 
-        ServerMessage::ShowRoundWinner(player_id) => {
-            state.round_winner = Some(player_id);
-            state.timer = Some(TimerTag::ShowRoundWinner.secs());
+        StatePatch::BotJoined(bot) => {
+            state.bots.push(bot.id.clone());
+            state.players.push(bot);
+        }
+
+        StatePatch::PlayerKicked(id) => {
+            state.players.retain(|p| p.id != id);
+            state.offline.retain(|p| *p != id);
+            state.bots.retain(|p| *p != id);
+        }
+
+        StatePatch::ConnectionChanged { id, connected } => {
+            state.offline.retain(|p| *p != id);
+            if !connected {
+                state.offline.push(id);
+            }
+        }
+
+        StatePatch::HostChanged(id) => {
+            state.host = Some(id);
+        }
+
+        StatePatch::AudienceJoined(new) => {
+            if let Some(p) = state.audience.iter_mut().find(|p| p.id == new.id) {
+                p.name = new.name;
+            } else {
+                state.audience.push(new);
+            }
+        }
+
+        StatePatch::PromptAdded(prompt) => {
+            state.player_prompts.push(prompt);
+        }
+
+        StatePatch::PromptRemoved(id) => {
+            state.player_prompts.retain(|p| p.id != id);
+        }
+
+        StatePatch::ConfigChanged(config) => {
+            // keep the host's newer edits, which are still on their way to the server
+            if state.config == local.synced_config {
+                state.config.clone_from(&config);
+            }
+            local.synced_config = config;
+        }
+
+        StatePatch::StepChanged { step, timer } => {
+            state.step = step;
+            state.timer = timer;
+        }
+
+        StatePatch::RoundStarted {
+            judge,
+            prompt,
+            round_counter,
+            timer,
+        } => {
+            local.hint = None;
+            *state = ClientGameState {
+                version: state.version,
+                step: GameStep::Submission,
+                judge,
+                prompt,
+                round_counter,
+                timer,
+                host: state.host.take(),
+                offline: std::mem::take(&mut state.offline),
+                bots: std::mem::take(&mut state.bots),
+                players: std::mem::take(&mut state.players),
+                audience: std::mem::take(&mut state.audience),
+                config: std::mem::take(&mut state.config),
+                ..Default::default()
+            };
         }
 
-        ServerMessage::IncrementSubmissionCount => {
+        StatePatch::IncrementSubmissionCount => {
             state.submission_count += 1;
         }
 
-        ServerMessage::UpdateRemainingTime(time) => {
-            state.timer = time;
+        StatePatch::SubmissionWithdrawn => {
+            state.submission_count = state.submission_count.saturating_sub(1);
+        }
+
+        StatePatch::SubmissionsRevealed(submissions) => {
+            state.submissions = submissions;
+        }
+
+        StatePatch::ShowRoundWinner { winner, authors } => {
+            state.round_winner = Some(winner);
+            state.authors = authors;
+            state.timer = Some(TimerTag::ShowRoundWinner.secs(&state.config));
         }
 
-        ServerMessage::DuplicateSession(_) => {}
+        StatePatch::IncrementVoteCount => {
+            state.vote_count += 1;
+        }
+
+        StatePatch::IncrementAudienceVoteCount => {
+            state.audience_vote_count += 1;
+        }
+
+        StatePatch::VotesRevealed {
+            winner,
+            tallies,
+            authors,
+        } => {
+            state.round_winner = winner;
+            state.vote_tallies = tallies;
+            state.authors = authors;
+            state.timer = Some(TimerTag::ShowRoundWinner.secs(&state.config));
+        }
+
+        StatePatch::ScoresPublished {
+            scores,
+            audience_bonuses,
+        } => {
+            state.scores = scores;
+            state.audience_bonuses = audience_bonuses;
+        }
     }
 }
@@ -1,4 +1,3 @@
-use crate::components::game::Game;
 use crate::components::pages;
 use leptos::*;
 use leptos_meta::*;
@@ -22,7 +21,7 @@ pub fn App() -> impl IntoView {
                     <Routes>
                         <Route
                             path="/"
-                            view=Game
+                            view=pages::Lobby
                         />
                         <Route
                             path="/lobby"
@@ -1,34 +1,55 @@
+mod audience;
 pub mod context;
 mod judging;
+mod notices;
 pub mod player_roster;
 mod prompt;
 mod results;
+mod scoring_rules;
 mod setup;
 mod submission;
 mod timer;
+mod voting;
+use self::audience::*;
 use self::context::*;
 use self::judging::*;
+use self::notices::*;
 use self::results::*;
 use self::setup::*;
 use self::submission::*;
+use self::voting::*;
 use crate::components::debug_view::*;
 use crate::components::state::*;
 use crate::types::*;
 use ::leptos::*;
 
 #[component]
-pub fn Game() -> impl IntoView {
-    provide_game_context();
+#[allow(clippy::needless_pass_by_value)]
+pub fn Game(room_code: RoomCode) -> impl IntoView {
+    provide_game_context(&room_code);
     let game_state = use_typed_context::<Signal_GameState>();
     let game_step = create_memo(move |_| game_state.with(|g| g.step.clone()));
+    let player_id = use_typed_context::<Signal_PlayerId>();
+    let players = use_typed_context::<Memo_Players>();
+    let is_audience = use_typed_context::<Memo_IsAudience>();
+    let is_spectating = move || {
+        game_step() != GameStep::Setup
+            && !is_audience()
+            && player_id.with(|me| players.with(|ps| ps.iter().all(|p| p.id != *me)))
+    };
 
     view! {
+        <Show when=is_spectating fallback=|| ()>
+            <AudienceSignup />
+        </Show>
         {move|| match game_step.get() {
             GameStep::Setup => view! { <GameSetup /> },
             GameStep::Submission => view! { <GameSubmission /> },
             GameStep::Judging => view! { <GameJudging />},
+            GameStep::Voting => view! { <GameVoting />},
             GameStep::Results => view! { <GameResults />},
         }}
+        <Notices />
         <DebugView />
     }
 }
@@ -0,0 +1,74 @@
+use super::context::*;
+use super::voting::Ballot;
+use crate::components::state::*;
+use crate::components::styles::*;
+use crate::types::ClientMessage::*;
+use ::leptos::*;
+
+/// Lets late joiners watch the game, since they can't play in it anymore
+#[component]
+pub fn AudienceSignup() -> impl IntoView {
+    let player_name = use_typed_context::<Signal_PlayerName>();
+    let action = create_ws_action();
+    let join = move || {
+        action.dispatch(JoinAudience {
+            name: player_name.get(),
+        });
+    };
+
+    view! {
+        <p>"This game has already started, but you can join the audience and vote for your favorites."</p>
+        <div class="flex flex-row gap-2">
+            <input
+                type="text"
+                class=text_input_class("")
+                value=player_name
+                on:input=move |e| player_name.set(event_target_value(&e))
+                on:keydown=move |e| if e.key() == "Enter" { join(); }
+            />
+            <button
+                class=ButtonStyle::Primary.class()
+                on:click=move|_| join()
+            >
+                "Join audience"
+            </button>
+        </div>
+    }
+}
+
+/// Audience members vote for their favorite, until the round winner is revealed
+#[component]
+pub fn AudienceBallot() -> impl IntoView {
+    let game_state = use_typed_context::<Signal_GameState>();
+    let is_open =
+        move || game_state.with(|g| g.round_winner.is_none() && g.vote_tallies.is_empty());
+    let bonus = move || game_state.with(|g| g.config.scoring.audience_bonus);
+    let show = move || is_open() && bonus() > 0;
+
+    view! {
+        <Show when=show fallback=|| ()>
+            <p>"Vote for your favorite. The audience's pick earns "{bonus}" bonus points."</p>
+            <Ballot vote=AudienceVote />
+        </Show>
+    }
+}
+
+#[component]
+pub fn AudienceStatus() -> impl IntoView {
+    let game_state = use_typed_context::<Signal_GameState>();
+    let audience_size = move || game_state.with(|g| g.audience.len());
+    let show = move || audience_size() > 0;
+
+    view! {
+        <Show when=show fallback=|| ()>
+            <p>
+                <span class=counter_class()>{audience_size}</span>
+                " in the audience, "
+                <span class=counter_class()>
+                    {move|| game_state.with(|g| g.audience_vote_count)}
+                </span>
+                " audience votes"
+            </p>
+        </Show>
+    }
+}
@@ -7,29 +7,69 @@ use typed_context::{define_context, provide_typed_context};
 use types::*;
 
 define_context!(Signal_GameState, RwSignal<ClientGameState>);
+define_context!(Signal_LocalState, RwSignal<LocalState>);
 define_context!(Signal_PlayerId, RwSignal<PlayerId>);
 define_context!(Signal_PlayerName, RwSignal<PlayerName>);
 define_context!(Memo_Players, Memo<Vec<Player>>);
 define_context!(Memo_Judge, Memo<Option<Judge>>);
 define_context!(Memo_IsHost, Memo<bool>);
+define_context!(Memo_IsAudience, Memo<bool>);
 define_context!(Memo_RoundCounter, Memo<String>);
 define_context!(TimerHandle, StoredValue<Option<IntervalHandle>>);
 
+/// Client side only, so it's kept apart from the game state the server sends
+#[derive(Clone, Debug, Default)]
+pub struct LocalState {
+    /// The last private hint from the server, cleared every round
+    pub hint: Option<String>,
+    /// The config as the server last saw it.
+    /// Differs from the game state's config while the host's edits are on their way to the server.
+    pub synced_config: Config,
+    /// Errors waiting to be shown, oldest first
+    pub toasts: Vec<Toast>,
+    /// Number of toasts ever shown, so each gets a unique id
+    pub toast_count: usize,
+}
+
+impl LocalState {
+    /// Queues a message to pop up for a few seconds
+    pub fn toast(&mut self, message: String) {
+        self.toasts.push(Toast {
+            id: self.toast_count,
+            message,
+        });
+        self.toast_count += 1;
+    }
+}
+
+/// A short lived notification
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toast {
+    pub id: usize,
+    pub message: String,
+}
+
 #[derive(PartialEq, Eq, Clone)]
 pub enum Judge {
     Me,
     Name(String),
+    /// The judge quit this round, so the host picks the winner instead
+    Left,
 }
 
-pub fn provide_game_context() {
+#[allow(unused_variables)]
+pub fn provide_game_context(room_code: &str) {
     let game_state = create_rw_signal(Default::default());
     provide_typed_context::<Signal_GameState>(game_state);
 
+    let local_state = create_rw_signal(LocalState::default());
+    provide_typed_context::<Signal_LocalState>(local_state);
+
     let player_id = signal_player_id();
     provide_typed_context::<Signal_PlayerId>(player_id);
 
     #[cfg(feature = "hydrate")]
-    crate::client::ws::connect_to_server(game_state, player_id);
+    crate::client::ws::connect_to_server(game_state, local_state, player_id, room_code);
 
     #[cfg(feature = "hydrate")]
     crate::client::timer::auto_sync_with_server();
@@ -53,6 +93,9 @@ pub fn provide_game_context() {
     let is_host = memo_is_host();
     provide_typed_context::<Memo_IsHost>(is_host);
 
+    let is_audience = memo_is_audience();
+    provide_typed_context::<Memo_IsAudience>(is_audience);
+
     let timer_handle = store_value(None);
     provide_typed_context::<TimerHandle>(timer_handle);
 
@@ -72,9 +115,11 @@ fn judge_memo() -> Memo<Option<Judge>> {
                     Some(Judge::Me)
                 } else {
                     players.with(|ps| {
-                        ps.iter()
-                            .find(|p| p.id == *judge_id)
-                            .map(|p| Judge::Name(p.name.clone()))
+                        Some(
+                            ps.iter()
+                                .find(|p| p.id == *judge_id)
+                                .map_or(Judge::Left, |p| Judge::Name(p.name.clone())),
+                        )
                     })
                 }
             })
@@ -82,23 +127,20 @@ fn judge_memo() -> Memo<Option<Judge>> {
     })
 }
 
-fn memo_is_host() -> Memo<bool> {
+fn memo_is_audience() -> Memo<bool> {
     let player_id = use_typed_context::<Signal_PlayerId>();
     let game_state = use_typed_context::<Signal_GameState>();
     create_memo(move |_| {
-        player_id
-            .with(|me| {
-                game_state
-                    .get()
-                    .players
-                    .first()
-                    .as_ref()
-                    .map(|p| p.id == *me)
-            })
-            .unwrap_or(false)
+        player_id.with(|me| game_state.with(|g| g.audience.iter().any(|p| p.id == *me)))
     })
 }
 
+fn memo_is_host() -> Memo<bool> {
+    let player_id = use_typed_context::<Signal_PlayerId>();
+    let game_state = use_typed_context::<Signal_GameState>();
+    create_memo(move |_| player_id.with(|me| game_state.with(|g| g.host.as_ref() == Some(me))))
+}
+
 #[cfg(not(feature = "hydrate"))]
 fn signal_player_id() -> RwSignal<PlayerId> {
     create_rw_signal(String::new())
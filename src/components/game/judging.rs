@@ -1,4 +1,4 @@
-use super::{context::*, prompt::*, timer::*};
+use super::{audience::*, context::*, prompt::*, timer::*};
 use crate::components::state::*;
 use crate::components::styles::*;
 use crate::typed_context::*;
@@ -12,6 +12,8 @@ pub fn GameJudging() -> impl IntoView {
     provide_player_lookup();
     let judge = use_typed_context::<Memo_Judge>();
     let round_counter = use_typed_context::<Memo_RoundCounter>();
+    let is_audience = use_typed_context::<Memo_IsAudience>();
+    let is_host = use_typed_context::<Memo_IsHost>();
     view! {
         <h2 class="text-l font-bold">
             {round_counter}
@@ -21,9 +23,22 @@ pub fn GameJudging() -> impl IntoView {
                 None => view! {<><span>"Error: No judge"</span></>},
                 Some(Judge::Me) => view! { <><JudgePerspective /></>},
                 Some(Judge::Name(name)) => view! { <><PlayerPerspective judge_name=name /></>},
+                Some(Judge::Left) if is_host() => view! {
+                    <>
+                        <p>"The judge left, so it's up to you to pick the winner."</p>
+                        <JudgePerspective />
+                    </>
+                },
+                Some(Judge::Left) => view! {
+                    <><PlayerPerspective judge_name="The host".to_owned() /></>
+                },
             }
         }
+        <Show when=is_audience fallback=|| ()>
+            <AudienceBallot />
+        </Show>
         <Timer />
+        <AudienceStatus />
     }
 }
 
@@ -37,7 +52,7 @@ fn JudgePerspective() -> impl IntoView {
         }
     };
 
-    let option_class = move |id: &PlayerId| {
+    let option_class = move |id: &SubmissionToken| {
         let id = id.clone();
         MaybeSignal::derive(move || {
             if selected.with(|s| s.as_ref() == Some(&id)) {
@@ -81,7 +96,7 @@ fn PlayerPerspective(judge_name: String) -> impl IntoView {
 #[component]
 fn Submissions<F1, F2>(disabled: bool, option_class: F1, on_select: F2) -> impl IntoView
 where
-    F1: 'static + Fn(&PlayerId) -> MaybeSignal<String>,
+    F1: 'static + Fn(&SubmissionToken) -> MaybeSignal<String>,
     F2: 'static + Copy + Fn(String),
 {
     use_typed_context::<Signal_GameState>()
@@ -114,32 +129,31 @@ where
         .collect::<Vec<_>>()
 }
 
-define_context!(LookupPlayer, Memo<HashMap<PlayerId, PlayerInfo>>);
+// Authors of each submission, empty until the winner is revealed
+define_context!(LookupPlayer, Memo<HashMap<SubmissionToken, PlayerInfo>>);
 fn provide_player_lookup() {
     let hashmap = create_memo(move |_| {
         use_typed_context::<Signal_GameState>().with(|g| {
-            g.round_winner
-                .as_ref()
-                .map_or(HashMap::with_capacity(0), |w| {
-                    let mut hashmap = HashMap::new();
-                    for p in &g.players {
-                        hashmap.insert(
-                            p.id.clone(),
-                            PlayerInfo {
-                                name: p.name.clone(),
-                                is_winner: p.id == *w,
-                            },
-                        );
-                    }
-                    hashmap
-                })
+            let mut hashmap = HashMap::new();
+            for (token, author) in &g.authors {
+                if let Some(p) = g.players.iter().find(|p| p.id == *author) {
+                    hashmap.insert(
+                        token.clone(),
+                        PlayerInfo {
+                            name: p.name.clone(),
+                            is_winner: g.round_winner.as_ref() == Some(author),
+                        },
+                    );
+                }
+            }
+            hashmap
         })
     });
 
     provide_typed_context::<LookupPlayer>(hashmap);
 }
 
-fn lookup(id: &PlayerId) -> Option<PlayerInfo> {
+fn lookup(id: &SubmissionToken) -> Option<PlayerInfo> {
     use_typed_context::<LookupPlayer>().with(|h| h.get(id).cloned())
 }
 
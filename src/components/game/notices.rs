@@ -0,0 +1,46 @@
+use super::context::*;
+use ::leptos::*;
+use ::std::time::Duration;
+
+/// How long an error stays on screen, unless it's dismissed sooner
+const TOAST_DURATION: Duration = Duration::from_secs(6);
+
+/// Private hints & errors from the server, meant only for this player
+#[component]
+pub fn Notices() -> impl IntoView {
+    let local_state = use_typed_context::<Signal_LocalState>();
+    let hint = move || local_state.with(|l| l.hint.clone());
+    let toasts = move || local_state.with(|l| l.toasts.clone());
+
+    view! {
+        {move || hint().map(|hint| view! { <p class="italic">{hint}</p> })}
+        <div class="fixed bottom-4 right-4 flex flex-col gap-2 items-end">
+            <For
+                each=toasts
+                key=|t| t.id
+                children=move |t| view! { <ToastView toast=t /> }
+            />
+        </div>
+    }
+}
+
+#[component]
+fn ToastView(toast: Toast) -> impl IntoView {
+    let local_state = use_typed_context::<Signal_LocalState>();
+    let id = toast.id;
+    let dismiss = move || local_state.update(|l| l.toasts.retain(|t| t.id != id));
+    // effects only run in the browser, where there's a clock to wait on
+    create_effect(move |_| set_timeout(dismiss, TOAST_DURATION));
+
+    view! {
+        <p class="rounded p-2 bg-slate-700 text-rose-400 shadow">
+            {toast.message}
+            <button
+                class="bg-slate-600 text-blue-50 rounded mx-2 px-2"
+                on:click=move|_| dismiss()
+            >
+                "Dismiss"
+            </button>
+        </p>
+    }
+}
@@ -27,15 +27,31 @@ pub fn PlayerRoster() -> impl IntoView {
 #[component]
 fn PlayerView(player: Player) -> impl IntoView {
     let player_id = use_typed_context::<Signal_PlayerId>();
+    let is_host = use_typed_context::<Memo_IsHost>();
+    let game_state = use_typed_context::<Signal_GameState>();
     let action = create_ws_action();
 
     let stored_id = store_value(player.id);
     let impersonate = move || player_id.set(stored_id.get_value());
     let kick = move || action.dispatch(KickPlayer(stored_id.get_value()));
+    let make_host = move || action.dispatch(TransferHost(stored_id.get_value()));
     let disabled_kick = move || stored_id.with_value(|id1| player_id.with(|id2| id1 == id2));
+    let is_player_host =
+        move || stored_id.with_value(|id| game_state.with(|g| g.host.as_ref() == Some(id)));
+    let is_offline = move || stored_id.with_value(|id| game_state.with(|g| g.offline.contains(id)));
+    let is_bot = move || stored_id.with_value(|id| game_state.with(|g| g.bots.contains(id)));
     view! {
         <li>
             {player.name}
+            <Show when=is_player_host fallback=|| ()>
+                <span class="italic px-2">"(host)"</span>
+            </Show>
+            <Show when=is_offline fallback=|| ()>
+                <span class="italic px-2 text-slate-400">"(offline)"</span>
+            </Show>
+            <Show when=is_bot fallback=|| ()>
+                <span class="italic px-2 text-slate-400">"(bot)"</span>
+            </Show>
             <Show
                 fallback=|| ()
                 when=|| DEV_MODE
@@ -47,13 +63,22 @@ fn PlayerView(player: Player) -> impl IntoView {
                     "Impersonate"
                 </button>
             </Show>
-            <button
-                class="bg-rose-400 text-blue-50 rounded mx-2 px-2 disabled:bg-slate-600"
-                disabled=disabled_kick
-                on:click=move|_| kick()
-            >
-                "Kick"
-            </button>
+            <Show when=is_host fallback=|| ()>
+                <button
+                    class="bg-rose-400 text-blue-50 rounded mx-2 px-2 disabled:bg-slate-600"
+                    disabled=disabled_kick
+                    on:click=move|_| kick()
+                >
+                    "Kick"
+                </button>
+                <button
+                    class="bg-cyan-500 text-blue-50 rounded mx-2 px-2 disabled:bg-slate-600"
+                    disabled=move|| disabled_kick() || is_bot()
+                    on:click=move|_| make_host()
+                >
+                    "Make host"
+                </button>
+            </Show>
         </li>
     }
 }
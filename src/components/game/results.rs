@@ -1,4 +1,6 @@
+use super::scoring_rules::*;
 use crate::components::game::context::*;
+use crate::components::styles::*;
 use ::leptos::*;
 
 #[component]
@@ -9,7 +11,12 @@ pub fn GameResults() -> impl IntoView {
         <table class="rounded border border-separate border-spacing-0 border-slate-400">
             <tbody>
                 {game_state.with(|g|
-                    g.scores.iter().enumerate().map(|(i, (name, score))|
+                    g.scores.iter().enumerate().map(|(i, (name, score))| {
+                        let audience_bonus = g
+                            .audience_bonuses
+                            .iter()
+                            .find(|(n, _)| n == name)
+                            .map_or(0, |(_, bonus)| *bonus);
                         view! {
                             <tr>
                                 <td
@@ -23,12 +30,18 @@ pub fn GameResults() -> impl IntoView {
                                     class=("border-t", i != 0)
                                 >
                                     {score.to_string()}
+                                    <Show when=move|| audience_bonus != 0 fallback=|| ()>
+                                        <span class=counter_class()>
+                                            " ("{audience_bonus}" from the audience)"
+                                        </span>
+                                    </Show>
                                 </td>
                             </tr>
                         }
-                    ).collect::<Vec<_>>()
+                    }).collect::<Vec<_>>()
                 )}
             </tbody>
         </table>
+        <ScoringRulesSummary />
     }
 }
@@ -0,0 +1,27 @@
+use super::context::*;
+use ::leptos::*;
+
+/// Lists how points are earned & lost this game
+#[component]
+pub fn ScoringRulesSummary() -> impl IntoView {
+    let game_state = use_typed_context::<Signal_GameState>();
+    let rules = move || {
+        game_state.with(|g| {
+            g.config
+                .scoring
+                .descriptions(g.config.mode)
+                .into_iter()
+                .map(|rule| view! { <li>{rule}</li> })
+                .collect::<Vec<_>>()
+        })
+    };
+
+    view! {
+        <details>
+            <summary class="cursor-pointer">"Scoring rules"</summary>
+            <ul class="list-inside list-disc flex flex-col items-start">
+                {rules}
+            </ul>
+        </details>
+    }
+}
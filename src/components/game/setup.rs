@@ -1,7 +1,13 @@
 use super::context::*;
+use super::scoring_rules::*;
 use crate::components::state::*;
 use crate::components::styles::*;
+use crate::constants::{MAX_PLAYERS, MAX_PROMPT_LENGTH, MIN_PLAYERS};
 use crate::types::ClientMessage::*;
+use crate::types::{
+    AcronymStyle, AlphabetPolicy, ClientGameState, Config, GameLength, GameMode,
+    LetterDistribution, PlayerPrompt, PromptPackInfo, ScoringRules, TimerSecs,
+};
 use ::leptos::*;
 
 #[component]
@@ -13,9 +19,19 @@ pub fn GameSetup() -> impl IntoView {
     let game_state = use_typed_context::<Signal_GameState>();
 
     let start_game_action = create_ws_action();
-    let start_game = move || {
-        let config = StartGame(game_state.with(|g| g.config.clone()));
-        start_game_action.dispatch(config);
+    let start_game = move || start_game_action.dispatch(StartGame);
+
+    share_config_edits();
+    let config_error = move || game_state.with(|g| g.config.validate().err());
+    let can_start = can_start();
+    let not_host = move || !is_host();
+
+    let audience = create_memo(move |_| game_state.with(|g| g.audience.clone()));
+    let join_audience_action = create_ws_action();
+    let join_audience = move || {
+        join_audience_action.dispatch(JoinAudience {
+            name: player_name.get(),
+        });
     };
 
     let join_game_action = create_ws_action();
@@ -41,10 +57,16 @@ pub fn GameSetup() -> impl IntoView {
             >
             {move|| if join_game_action.version().get() > 0 { "Update name" } else { "Join" }}
             </button>
+            <button
+                class=ButtonStyle::Neutral.class()
+                on:click=move|_| join_audience()
+            >
+                "Watch"
+            </button>
             <Show when=is_host fallback=|| ()>
                 <button
                     class=ButtonStyle::Secondary.class()
-                    disabled=move|| players.with(|ps| ps.len() < 3)
+                    disabled=move|| !can_start()
                     on:click=move|_| start_game()
                 >
                     "Start game"
@@ -57,19 +79,343 @@ pub fn GameSetup() -> impl IntoView {
                 <For
                     each=players
                     key=|p| format!("{}-{}", p.id, p.name)
-                    children=|p| view! { <li>{p.name}</li> }
+                    children=move |p| {
+                        let is_bot = game_state.with_untracked(|g| g.bots.contains(&p.id));
+                        view! { <li>{p.name}{is_bot.then_some(" (bot)")}</li> }
+                    }
                 />
             </ul>
+            <ManageBots />
+            <Show when=move|| audience.with(|a| !a.is_empty()) fallback=|| ()>
+                <p>{move || audience.with(|a| a.len())}" in the audience"</p>
+                <ul class="list-inside list-disc flex flex-col items-start">
+                    <For
+                        each=audience
+                        key=|p| format!("{}-{}", p.id, p.name)
+                        children=|p| view! { <li>{p.name}</li> }
+                    />
+                </ul>
+            </Show>
         </div>
+        <h1 class="text-xl font-bold">"Prompts"</h1>
+        // only the host can change the config, everyone else just sees it
+        <fieldset class="contents" disabled=not_host>
+            <ConfigurePromptPacks />
+        </fieldset>
+        <PlayerPrompts />
         <h1 class="text-xl font-bold">"Configuration"</h1>
-        <ConfigureAcronymLength />
+        {move || config_error().map(|e| view! { <p class="text-rose-400">{e}</p> })}
+        <fieldset class="contents" disabled=not_host>
+            <ConfigureGameMode />
+            <ConfigureAcronymLength />
+            <ConfigureLetterDistribution />
+            <ConfigureAcronymStyle />
+            <ConfigureAlphabet />
+            <ConfigureGameLength />
+            <ConfigureTimers />
+            <ConfigureScoring />
+        </fieldset>
     }
 }
 
+/// Bots fill out a game that's short on players.
+/// They're removed newest first. Only the host sees these buttons.
 #[component]
-pub fn ConfigureAcronymLength() -> impl IntoView {
-    const MIN_ACRONYM_LENGTH: usize = 2;
+fn ManageBots() -> impl IntoView {
+    let is_host = use_typed_context::<Memo_IsHost>();
+    let game_state = use_typed_context::<Signal_GameState>();
+    let action = create_ws_action();
+
+    let is_full = move || game_state.with(|g| g.players.len() >= MAX_PLAYERS);
+    let newest_bot = move || game_state.with(|g| g.bots.last().cloned());
+    let add = move || action.dispatch(AddBot);
+    let remove = move || {
+        if let Some(id) = newest_bot() {
+            action.dispatch(KickPlayer(id));
+        }
+    };
+
+    view! {
+        <Show when=is_host fallback=|| ()>
+            <div class="flex flex-row gap-4">
+                <button
+                    class=ButtonStyle::Neutral.class()
+                    disabled=is_full
+                    on:click=move|_| add()
+                >
+                    "Add bot"
+                </button>
+                <button
+                    class=ButtonStyle::Neutral.class()
+                    disabled=move|| newest_bot().is_none()
+                    on:click=move|_| remove()
+                >
+                    "Remove bot"
+                </button>
+            </div>
+        </Show>
+    }
+}
+
+/// Enough players joined, and the server has the host's latest config
+fn can_start() -> impl Fn() -> bool + Copy {
+    let players = use_typed_context::<Memo_Players>();
+    let game_state = use_typed_context::<Signal_GameState>();
+    let local_state = use_typed_context::<Signal_LocalState>();
+    move || {
+        players.with(|ps| ps.len() >= MIN_PLAYERS)
+            && game_state.with(|g| {
+                local_state.with(|l| g.config == l.synced_config) && g.config.validate().is_ok()
+            })
+    }
+}
+
+/// The host's edits are shared live, so everyone sees the config before the game starts
+fn share_config_edits() {
+    let is_host = use_typed_context::<Memo_IsHost>();
+    let game_state = use_typed_context::<Signal_GameState>();
+    let local_state = use_typed_context::<Signal_LocalState>();
+    let update_config_action = create_ws_action();
+    create_effect(move |_| {
+        let edited = game_state.with(|g| {
+            let synced = local_state.with(|l| g.config == l.synced_config);
+            (!synced && g.config.validate().is_ok()).then(|| g.config.clone())
+        });
+        if let Some(config) = edited
+            && is_host.get_untracked()
+        {
+            update_config_action.dispatch(UpdateConfig(config));
+        }
+    });
+}
+
+#[server(PromptPacks, "/api")]
+pub async fn prompt_packs() -> Result<Vec<PromptPackInfo>, ServerFnError> {
+    Ok(crate::server::prompt_packs::infos())
+}
+
+/// Prompts are drawn from every pack that's checked
+#[component]
+pub fn ConfigurePromptPacks() -> impl IntoView {
+    let g = use_typed_context::<Signal_GameState>();
+    let (selected, set_selected) = create_slice(
+        g,
+        move |g| g.config.prompt_packs.clone(),
+        move |g, v| g.config.prompt_packs = v,
+    );
+    let packs = create_resource(|| (), |()| prompt_packs());
+
+    let toggle = move |id: String, checked: bool| {
+        let mut ids = selected();
+        ids.retain(|i| *i != id);
+        if checked {
+            ids.push(id);
+        }
+        set_selected.set(ids);
+    };
+
+    let pack_view = move |pack: PromptPackInfo| {
+        let id = store_value(pack.id);
+        view! {
+            <label class="flex flex-row gap-2 items-center">
+                <input
+                    type="checkbox"
+                    prop:checked=move|| id.with_value(|id| selected().contains(id))
+                    on:change=move|e| toggle(id.get_value(), event_target_checked(&e))
+                />
+                <span class="font-bold">{pack.name}</span>
+                {format!("({}, {} prompts)", pack.rating.name(), pack.prompt_count)}
+                <span class="italic">{pack.description}</span>
+            </label>
+        }
+    };
+
+    view! {
+        <Suspense fallback=|| ()>
+            <div class="flex flex-col gap-2 items-start">
+                {move || packs.get().map(|packs| {
+                    packs
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(pack_view)
+                        .collect::<Vec<_>>()
+                })}
+            </div>
+        </Suspense>
+    }
+}
+
+/// Players can write their own prompts, which get shuffled into the deck when the game starts.
+/// The host can remove any they don't want to play with.
+#[component]
+pub fn PlayerPrompts() -> impl IntoView {
+    let game_state = use_typed_context::<Signal_GameState>();
+    let prompts = create_memo(move |_| game_state.with(|g| g.player_prompts.clone()));
+
+    let template = create_rw_signal(String::new());
+    // same rule the server uses: exactly one blank
+    let is_valid = move || template.with(|t| t.split("___").count() == 2);
+
+    let submit_action = create_ws_action();
+    let submit = move || {
+        if is_valid() {
+            submit_action.dispatch(SubmitPrompt(template.get()));
+            template.set(String::new());
+        }
+    };
+
+    view! {
+        <p>"Write your own prompt, with ___ where the acronym goes"</p>
+        <div class="flex flex-row gap-2">
+            <input
+                type="text"
+                class=text_input_class("")
+                placeholder="In case of emergency, remember ___"
+                maxlength=MAX_PROMPT_LENGTH
+                prop:value=template
+                on:input=move |e| template.set(event_target_value(&e))
+                on:keydown=move |e| if e.key() == "Enter" { submit(); }
+            />
+            <button
+                class=ButtonStyle::Secondary.class()
+                disabled=move|| !is_valid()
+                on:click=move|_| submit()
+            >
+                "Add prompt"
+            </button>
+        </div>
+        <ul class="list-inside list-disc flex flex-col items-start">
+            <For
+                each=prompts
+                key=|p| p.id.clone()
+                children=|p| view! { <PlayerPromptItem prompt=p /> }
+            />
+        </ul>
+    }
+}
+
+#[component]
+fn PlayerPromptItem(prompt: PlayerPrompt) -> impl IntoView {
+    let is_host = use_typed_context::<Memo_IsHost>();
+    let player_id = use_typed_context::<Signal_PlayerId>();
+    let action = create_ws_action();
+
+    // the author or the host can remove a prompt
+    let author = prompt.author;
+    let can_remove = move || is_host() || player_id.with(|me| *me == author);
+    let stored_id = store_value(prompt.id);
+    let remove = move || action.dispatch(RemovePrompt(stored_id.get_value()));
+    view! {
+        <li>
+            {prompt.before}
+            <span class="font-bold">"___"</span>
+            {prompt.after}
+            <Show when=can_remove fallback=|| ()>
+                <button
+                    class="bg-rose-400 text-blue-50 rounded mx-2 px-2"
+                    on:click=move|_| remove()
+                >
+                    "Remove"
+                </button>
+            </Show>
+        </li>
+    }
+}
+
+#[component]
+pub fn ConfigureGameMode() -> impl IntoView {
+    let g = use_typed_context::<Signal_GameState>();
+    let (mode, set_mode) = create_slice(g, move |g| g.config.mode, move |g, v| g.config.mode = v);
+
+    let option = move |value: GameMode, label: &'static str| {
+        view! {
+            <label class="flex flex-row gap-2 items-center">
+                <input
+                    type="radio"
+                    name="game-mode"
+                    prop:checked=move|| mode() == value
+                    on:change=move|_| set_mode.set(value)
+                />
+                {label}
+            </label>
+        }
+    };
+
+    view! {
+        <div class="flex flex-col gap-2 items-start">
+            {option(GameMode::Judge, "Judge: each round one player picks the winner")}
+            {option(GameMode::HeadToHead, "Head to head: everyone votes for their favorite")}
+        </div>
+    }
+}
+
+#[component]
+pub fn ConfigureAcronymStyle() -> impl IntoView {
+    let g = use_typed_context::<Signal_GameState>();
+    let (style, set_style) = create_slice(
+        g,
+        move |g| g.config.acronym_style,
+        move |g, v| g.config.acronym_style = v,
+    );
+
+    let option = move |value: AcronymStyle, label: &'static str| {
+        view! {
+            <label class="flex flex-row gap-2 items-center">
+                <input
+                    type="radio"
+                    name="acronym-style"
+                    prop:checked=move|| style() == value
+                    on:change=move|_| set_style.set(value)
+                />
+                {label}
+            </label>
+        }
+    };
+
+    view! {
+        <div class="flex flex-col gap-2 items-start">
+            {option(AcronymStyle::Initialism, "Initialisms (any letters)")}
+            {option(AcronymStyle::Pronounceable, "True acronyms (pronounceable)")}
+        </div>
+    }
+}
+
+/// Which characters submissions can use
+#[component]
+pub fn ConfigureAlphabet() -> impl IntoView {
+    let g = use_typed_context::<Signal_GameState>();
+    let (alphabet, set_alphabet) = create_slice(
+        g,
+        move |g| g.config.alphabet,
+        move |g, v| g.config.alphabet = v,
+    );
+
+    let option = move |value: AlphabetPolicy| {
+        view! {
+            <label class="flex flex-row gap-2 items-center">
+                <input
+                    type="radio"
+                    name="alphabet"
+                    prop:checked=move|| alphabet() == value
+                    on:change=move|_| set_alphabet.set(value)
+                />
+                {value.description()}
+            </label>
+        }
+    };
 
+    view! {
+        <div class="flex flex-col gap-2 items-start">
+            <p>"Submissions can use:"</p>
+            {option(AlphabetPolicy::Anything)}
+            {option(AlphabetPolicy::Words)}
+            {option(AlphabetPolicy::LettersOnly)}
+        </div>
+    }
+}
+
+#[component]
+pub fn ConfigureAcronymLength() -> impl IntoView {
     let g = use_typed_context::<Signal_GameState>();
     let (min, set_min) = create_slice(
         g,
@@ -88,7 +434,7 @@ pub fn ConfigureAcronymLength() -> impl IntoView {
             <input
                 type="number"
                 class=number_input_class("w-[4rem]")
-                min=MIN_ACRONYM_LENGTH
+                min=Config::MIN_LETTERS
                 prop:max=max
                 prop:value=min
                 on:change=move|e| {
@@ -102,6 +448,7 @@ pub fn ConfigureAcronymLength() -> impl IntoView {
                 type="number"
                 class=number_input_class("w-[4rem]")
                 prop:min=min
+                max=Config::MAX_LETTERS
                 prop:value=max
                 on:input=move|e| {
                     if let Ok(n) = event_target_value(&e).parse() {
@@ -113,3 +460,311 @@ pub fn ConfigureAcronymLength() -> impl IntoView {
         </div>
     }
 }
+
+#[component]
+pub fn ConfigureScoring() -> impl IntoView {
+    let g = use_typed_context::<Signal_GameState>();
+    let (scoring, set_scoring) = create_slice(
+        g,
+        move |g| g.config.scoring,
+        move |g, v| g.config.scoring = v,
+    );
+
+    let option = move |label: &'static str, field: fn(&mut ScoringRules) -> &mut bool| {
+        view! {
+            <label class="flex flex-row gap-2 items-center">
+                <input
+                    type="checkbox"
+                    prop:checked=move|| *field(&mut scoring())
+                    on:change=move|e| {
+                        let mut rules = scoring();
+                        *field(&mut rules) = event_target_checked(&e);
+                        set_scoring.set(rules);
+                    }
+                />
+                {label}
+            </label>
+        }
+    };
+
+    view! {
+        <div class="flex flex-col gap-2 items-start">
+            {option("Penalize characters that aren't letters", |r| &mut r.character_penalty)}
+            {option("Penalize the judge for running out of time", |r| &mut r.judge_timeout_penalty)}
+            {option("Double points in the second half", |r| &mut r.double_points_second_half)}
+            <div class="flex flex-row gap-2 items-start">
+                "The audience's favorite earns"
+                <input
+                    type="number"
+                    class=number_input_class("w-[4rem]")
+                    min=0
+                    prop:value=move|| scoring().audience_bonus
+                    on:change=move|e| {
+                        if let Ok(n) = event_target_value(&e).parse::<i64>() {
+                            let mut rules = scoring();
+                            rules.audience_bonus = n.max(0);
+                            set_scoring.set(rules);
+                        }
+                    }
+                />
+                "bonus points"
+            </div>
+        </div>
+        <ScoringRulesSummary />
+    }
+}
+
+#[component]
+pub fn ConfigureTimers() -> impl IntoView {
+    let g = use_typed_context::<Signal_GameState>();
+
+    let input =
+        move |label: &'static str, get: fn(&TimerSecs) -> u64, set: fn(&mut TimerSecs, u64)| {
+            let (secs, set_secs) = create_slice(
+                g,
+                move |g| get(&g.config.timer_secs),
+                move |g, v| set(&mut g.config.timer_secs, v),
+            );
+            view! {
+                <div class="flex flex-row gap-2 items-start">
+                    <input
+                        type="number"
+                        class=number_input_class("w-[5rem]")
+                        min=TimerSecs::MIN
+                        max=TimerSecs::MAX
+                        prop:value=secs
+                        on:change=move|e| {
+                            if let Ok(n) = event_target_value(&e).parse::<u64>() {
+                                set_secs.set(n.clamp(TimerSecs::MIN, TimerSecs::MAX));
+                            }
+                        }
+                    />
+                    {label}
+                </div>
+            }
+        };
+
+    view! {
+        <div class="flex flex-col gap-2 items-start">
+            {input("seconds for submissions", |t| t.submission, |t, v| t.submission = v)}
+            {input("seconds for judging or voting", |t| t.judging, |t, v| t.judging = v)}
+            {input("seconds to show the round winner", |t| t.round_winner, |t, v| t.round_winner = v)}
+            {input("seconds to reconnect, before a player is out of the game", |t| t.reconnect_grace, |t, v| t.reconnect_grace = v)}
+        </div>
+    }
+}
+
+#[component]
+pub fn ConfigureGameLength() -> impl IntoView {
+    const DEFAULT_TARGET_SCORE: i64 = 20;
+
+    let g = use_typed_context::<Signal_GameState>();
+    let (length, set_length) = create_slice(
+        g,
+        move |g| g.config.game_length,
+        move |g, v| g.config.game_length = v,
+    );
+    let (target, set_target) = create_slice(
+        g,
+        move |g| g.config.target_score,
+        move |g, v| g.config.target_score = v,
+    );
+
+    let rounds = move || match length() {
+        GameLength::RoundsPerJudge(n) | GameLength::Rounds(n) => n,
+    };
+    let per_judge = move || matches!(length(), GameLength::RoundsPerJudge(_));
+
+    view! {
+        <div class="flex flex-row gap-2 items-start">
+            "Play"
+            <input
+                type="number"
+                class=number_input_class("w-[4rem]")
+                min=1
+                prop:value=rounds
+                on:change=move|e| {
+                    if let Ok(n) = event_target_value(&e).parse::<usize>() {
+                        let n = n.max(1);
+                        set_length.set(if per_judge() {
+                            GameLength::RoundsPerJudge(n)
+                        } else {
+                            GameLength::Rounds(n)
+                        });
+                    }
+                }
+            />
+            <select
+                class=number_input_class("")
+                on:change=move|e| {
+                    let n = rounds();
+                    set_length.set(if event_target_value(&e) == "per-judge" {
+                        GameLength::RoundsPerJudge(n)
+                    } else {
+                        GameLength::Rounds(n)
+                    });
+                }
+            >
+                <option value="per-judge" selected=per_judge>"rounds per judge"</option>
+                <option value="total" selected=move|| !per_judge()>"rounds in total"</option>
+            </select>
+        </div>
+        <div class="flex flex-row gap-2 items-start">
+            <input
+                type="checkbox"
+                prop:checked=move|| target().is_some()
+                on:change=move|e| {
+                    set_target.set(event_target_checked(&e).then_some(DEFAULT_TARGET_SCORE));
+                }
+            />
+            "End early when someone reaches"
+            <input
+                type="number"
+                class=number_input_class("w-[4rem]")
+                min=1
+                disabled=move|| target().is_none()
+                prop:value=move|| target().unwrap_or(DEFAULT_TARGET_SCORE)
+                on:change=move|e| {
+                    if let Ok(n) = event_target_value(&e).parse::<i64>() {
+                        set_target.set(Some(n.max(1)));
+                    }
+                }
+            />
+            "points"
+        </div>
+    }
+}
+
+#[component]
+pub fn ConfigureLetterDistribution() -> impl IntoView {
+    let g = use_typed_context::<Signal_GameState>();
+    let (distribution, set_distribution) = create_slice(
+        g,
+        move |g| g.config.letter_distribution.clone(),
+        move |g, v| g.config.letter_distribution = v,
+    );
+    let is_custom = move || matches!(distribution(), LetterDistribution::Custom(_));
+
+    let on_change = move |name: String| {
+        let choice = LetterDistribution::PRESETS
+            .into_iter()
+            .find(|d| d.name() == name)
+            .unwrap_or_else(|| {
+                // custom weights start off from whatever was picked before
+                let weights =
+                    g.with_untracked(|g| g.config.letter_distribution.weights().into_owned());
+                LetterDistribution::Custom(weights)
+            });
+        set_distribution.set(choice);
+    };
+
+    let presets = LetterDistribution::PRESETS
+        .into_iter()
+        .map(|d| {
+            let name = d.name();
+            view! {
+                <option value=name selected=move|| distribution().name() == name>
+                    {name}
+                </option>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    view! {
+        <div class="flex flex-row gap-2 items-start">
+            "Letters are drawn from"
+            <select
+                class=number_input_class("")
+                on:change=move|e| on_change(event_target_value(&e))
+            >
+                {presets}
+                <option value="Custom" selected=is_custom>"Custom"</option>
+            </select>
+            "frequencies"
+        </div>
+        <Show when=is_custom fallback=|| ()>
+            <CustomLetterWeights />
+        </Show>
+    }
+}
+
+/// Editor for the relative weight of each letter.
+/// A weight of 0 means the letter is never drawn.
+#[component]
+fn CustomLetterWeights() -> impl IntoView {
+    const NEW_LETTER_WEIGHT: usize = 100;
+
+    let g = use_typed_context::<Signal_GameState>();
+    let weights =
+        create_memo(move |_| g.with(|g| g.config.letter_distribution.weights().into_owned()));
+    let new_letter = create_rw_signal(String::new());
+    let add_letter = move || {
+        let letter = new_letter
+            .get_untracked()
+            .chars()
+            .find(|c| c.is_alphabetic())
+            .and_then(|c| c.to_uppercase().next())
+            .filter(|c| LetterDistribution::is_allowed_letter(*c));
+        if let Some(letter) = letter {
+            update_custom_weights(g, |weights| {
+                if !weights.iter().any(|(c, _)| *c == letter) {
+                    weights.push((letter, NEW_LETTER_WEIGHT));
+                }
+            });
+        }
+        new_letter.set(String::new());
+    };
+
+    view! {
+        <div class="grid grid-cols-6 gap-2">
+            <For
+                each=weights
+                key=|(letter, _)| *letter
+                children=move |(letter, weight)| view! {
+                    <label class="flex flex-row gap-1 items-center">
+                        <span class="font-bold w-4">{letter.to_string()}</span>
+                        <input
+                            type="number"
+                            class=number_input_class("w-[5rem]")
+                            min=0
+                            prop:value=weight
+                            on:change=move|e| {
+                                if let Ok(n) = event_target_value(&e).parse::<usize>() {
+                                    update_custom_weights(g, |weights| {
+                                        if let Some(w) = weights.iter_mut().find(|(c, _)| *c == letter) {
+                                            w.1 = n.min(LetterDistribution::MAX_WEIGHT);
+                                        }
+                                    });
+                                }
+                            }
+                        />
+                    </label>
+                }
+            />
+        </div>
+        <div class="flex flex-row gap-2 items-start">
+            <input
+                type="text"
+                maxlength=1
+                class=text_input_class("w-[3rem]")
+                prop:value=new_letter
+                on:input=move|e| new_letter.set(event_target_value(&e))
+                on:keydown=move|e| if e.key() == "Enter" { add_letter(); }
+            />
+            <button
+                class=ButtonStyle::Neutral.class()
+                on:click=move|_| add_letter()
+            >
+                "Add letter"
+            </button>
+        </div>
+    }
+}
+
+fn update_custom_weights(g: RwSignal<ClientGameState>, f: impl FnOnce(&mut Vec<(char, usize)>)) {
+    g.update(|g| {
+        if let LetterDistribution::Custom(weights) = &mut g.config.letter_distribution {
+            f(weights);
+        }
+    });
+}
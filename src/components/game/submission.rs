@@ -1,16 +1,34 @@
-use super::{context::*, prompt::*, timer::*};
+use super::{context::*, prompt::*, scoring_rules::*, timer::*};
 use crate::components::state::*;
 use crate::components::styles::*;
 use crate::types::ClientMessage::*;
+use crate::types::{validate_word, GameMode};
 use ::leptos::*;
 
 #[component]
 pub fn GameSubmission() -> impl IntoView {
     let judge = use_typed_context::<Memo_Judge>();
+    let is_host = use_typed_context::<Memo_IsHost>();
     let round_counter = use_typed_context::<Memo_RoundCounter>();
     let game_state = use_typed_context::<Signal_GameState>();
+    // the host fills in for a judge who left
+    let is_judging = move || match judge.get() {
+        Some(Judge::Me) => true,
+        Some(Judge::Left) => is_host(),
+        _ => false,
+    };
+    let head_to_head =
+        create_memo(move |_| game_state.with(|g| g.config.mode == GameMode::HeadToHead));
     let submission_ratio = create_memo(move |_| {
-        game_state.with(|g| format!("{}/{}", g.submission_count, g.players.len() - 1))
+        game_state.with(|g| {
+            // the judge doesn't submit
+            let expected = if head_to_head() {
+                g.players.len()
+            } else {
+                g.players.len() - 1
+            };
+            format!("{}/{}", g.submission_count, expected)
+        })
     });
 
     view! {
@@ -18,23 +36,30 @@ pub fn GameSubmission() -> impl IntoView {
             {round_counter}
         </h2>
         <Prompt/>
-        <Show when=move|| judge.get() != Some(Judge::Me) fallback=move|| () >
+        <Show when=move|| !is_judging() fallback=move|| () >
             <PlayerPerspective />
         </Show>
         <Timer/>
-        <JudgeDescription/>
+        <Show
+            when=move|| !head_to_head()
+            fallback=|| view! { <p>"Everyone votes for their favorite once all submissions are in."</p> }
+        >
+            <JudgeDescription/>
+        </Show>
         <p>
             <span class="text-pink-100">
                 {submission_ratio}
             </span>
             " submissions received"
         </p>
+        <ScoringRulesSummary />
     }
 }
 
 #[component]
 fn JudgeDescription() -> impl IntoView {
     let judge = use_typed_context::<Memo_Judge>();
+    let is_host = use_typed_context::<Memo_IsHost>();
     move || match judge.get() {
         None => view! {<p>"Error: No judge"</p>},
         Some(Judge::Me) => view! {
@@ -47,6 +72,12 @@ fn JudgeDescription() -> impl IntoView {
                 <span class=judge_class()>{name}</span>" is the judge."
             </p>
         },
+        Some(Judge::Left) if is_host() => view! {
+            <p>"The judge left, so it's up to you to pick the winner."</p>
+        },
+        Some(Judge::Left) => view! {
+            <p>"The judge left. The host will pick the winner."</p>
+        },
     }
 }
 
@@ -55,7 +86,7 @@ fn PlayerPerspective() -> impl IntoView {
     let game_state = use_typed_context::<Signal_GameState>();
     let acronym = create_memo(move |_| game_state.with(|g| g.prompt.acronym.clone()));
 
-    let num_of_words = acronym().len();
+    let num_of_words = acronym().chars().count();
     let input_refs = store_value(init_vec(num_of_words, move || {
         create_node_ref::<html::Input>()
     }));
@@ -98,7 +129,8 @@ fn PlayerPerspective() -> impl IntoView {
                         on:input=move |e| {
                             let input: web_sys::HtmlInputElement = event_target(&e);
                             let text = input.value();
-                            let result = validate_word(c, &text);
+                            let result = game_state
+                                .with_untracked(|g| validate_word(c, &text, &g.config));
                             match &result {
                                 Ok(()) => {
                                     input.set_custom_validity("");
@@ -160,24 +192,3 @@ fn all_some<T: Clone>(v: &[Option<T>]) -> Option<Vec<T>> {
             .collect::<Vec<_>>(),
     )
 }
-
-#[cfg(not(feature = "hydrate"))]
-fn validate_word(_lead: char, _word: &str) -> Result<(), String> {
-    Ok(())
-}
-
-/// Validates leading character.
-/// TODO: Should we enforce alphanumeric characters?
-#[cfg(feature = "hydrate")]
-fn validate_word(lead: char, word: &str) -> Result<(), String> {
-    use js_sys::RegExp;
-    let pattern = RegExp::new(&format!("^{}", lead), "i");
-    if pattern.exec(word).is_some() {
-        Ok(())
-    } else {
-        Err(format!(
-            "Should start with {}",
-            lead.to_uppercase().collect::<String>(),
-        ))
-    }
-}
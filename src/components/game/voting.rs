@@ -0,0 +1,159 @@
+use super::{audience::*, context::*, prompt::*, timer::*};
+use crate::components::state::*;
+use crate::components::styles::*;
+use crate::types::ClientMessage::*;
+use crate::types::*;
+use leptos::*;
+
+/// Head to head mode's replacement for the judging step.
+/// Everyone votes for their favorite submission, other than their own.
+#[component]
+pub fn GameVoting() -> impl IntoView {
+    let round_counter = use_typed_context::<Memo_RoundCounter>();
+    let game_state = use_typed_context::<Signal_GameState>();
+    let is_audience = use_typed_context::<Memo_IsAudience>();
+    let voting_closed = create_memo(move |_| game_state.with(|g| !g.vote_tallies.is_empty()));
+    let vote_ratio =
+        create_memo(move |_| game_state.with(|g| format!("{}/{}", g.vote_count, g.players.len())));
+
+    view! {
+        <h2 class="text-l font-bold">
+            {round_counter}
+        </h2>
+        <Prompt />
+        {
+            move || if voting_closed() {
+                view! { <><VoteResults /></> }
+            } else if is_audience() {
+                view! { <><AudienceBallot /></> }
+            } else {
+                view! { <><Ballot vote=CastVote /></> }
+            }
+        }
+        <Timer />
+        <p>
+            <span class="text-pink-100">
+                {vote_ratio}
+            </span>
+            " votes received"
+        </p>
+        <AudienceStatus />
+    }
+}
+
+/// Pick a favorite submission, other than your own
+#[component]
+pub fn Ballot(
+    /// The message to send with the chosen submission
+    vote: fn(SubmissionToken) -> ClientMessage,
+) -> impl IntoView {
+    let game_state = use_typed_context::<Signal_GameState>();
+    let selected = create_rw_signal(None);
+    let vote_action = create_ws_action();
+    let last_vote = store_value(None as Option<String>);
+    let vote = move || {
+        if let Some((id, words)) = selected() {
+            last_vote.set_value(Some(words));
+            vote_action.dispatch(vote(id));
+        }
+    };
+
+    let submissions = game_state
+        .with(|g| g.submissions.clone())
+        .into_iter()
+        .map(|(token, words)| {
+            let words = words.join(" ");
+            let stored = store_value((token, words.clone()));
+            let is_mine = move || {
+                stored.with_value(|(token, _)| {
+                    game_state.with(|g| g.own_submission.as_ref() == Some(token))
+                })
+            };
+            let is_selected = move || {
+                stored.with_value(|(id, _)| {
+                    selected.with(|s: &Option<(SubmissionToken, String)>| {
+                        s.as_ref().is_some_and(|(s, _)| s == id)
+                    })
+                })
+            };
+            let class = move || {
+                ButtonStyle::Nothing.class_with(if is_selected() {
+                    "bg-cyan-600"
+                } else {
+                    "bg-slate-600 hover:bg-cyan-500"
+                })
+            };
+            view! {
+                <button
+                    class=class
+                    disabled=is_mine
+                    on:click=move|_| selected.set(Some(stored.get_value()))
+                >
+                    {words}
+                </button>
+            }
+        })
+        .collect::<Vec<_>>();
+
+    view! {
+        <div class="flex flex-col gap-2 justify-content">
+            {submissions}
+        </div>
+        <div>
+            <button
+                class=ButtonStyle::Secondary.class()
+                disabled=move|| selected.with(|s| s.is_none())
+                on:click=move|_| vote()
+            >
+                "Vote"
+            </button>
+            <span class="px-2">
+                {move|| if vote_action.version().get() > 0 {
+                    last_vote.get_value().map(|s|
+                        view! {
+                            <span>
+                                "voted for: "
+                                <span class="font-bold">{s}</span>
+                            </span>
+                        }
+                    )
+                } else {
+                    None
+                }}
+            </span>
+        </div>
+    }
+}
+
+#[component]
+fn VoteResults() -> impl IntoView {
+    use_typed_context::<Signal_GameState>().with(|g| {
+        g.submissions
+            .iter()
+            .map(|(token, words)| {
+                let votes = g
+                    .vote_tallies
+                    .iter()
+                    .find(|(candidate, _)| candidate == token)
+                    .map_or(0, |(_, votes)| *votes);
+                let author = g.authors.iter().find(|(t, _)| t == token).map(|(_, id)| id);
+                let name = g
+                    .players
+                    .iter()
+                    .find(|p| Some(&p.id) == author)
+                    .map(|p| p.name.clone());
+                let is_winner = author.is_some() && g.round_winner.as_ref() == author;
+                view! {
+                    <div class="flex flex-col justify-content">
+                        <span class="rounded p-2 bg-slate-600">{words.join(" ")}</span>
+                        <div class="font-bold">
+                            {is_winner.then_some("👑 ")}{name}
+                            <span class=counter_class()>" +"{votes}</span>
+                            {if votes == 1 { " vote" } else { " votes" }}
+                        </div>
+                    </div>
+                }
+            })
+            .collect::<Vec<_>>()
+    })
+}
@@ -1,25 +1,26 @@
 use crate::components::styles::*;
+use crate::types::RoomCode;
 use ::leptos::*;
 use ::leptos_router::*;
 
 #[component]
 pub fn Lobby() -> impl IntoView {
-    #[cfg(not(feature = "hydrate"))]
-    let on_click = move |_| ();
+    let create_room = create_server_action::<CreateRoom>();
+    let navigate = use_navigate();
 
-    #[cfg(feature = "hydrate")]
-    let on_click = move |_| {
-        use ::uuid::*;
-        let code = Uuid::new_v4().to_string();
-        let nav = use_navigate();
-        nav(&format!("room/{}", code), NavigateOptions::default());
-    };
+    // join the room as soon as the server has made it
+    create_effect(move |_| {
+        if let Some(Ok(code)) = create_room.value().get() {
+            navigate(&format!("/room/{}", code), NavigateOptions::default());
+        }
+    });
 
     view! {
         Welcome to the lobby!
         <button
             class=ButtonStyle::Primary.class()
-            on:click=on_click
+            disabled=create_room.pending()
+            on:click=move |_| create_room.dispatch(CreateRoom {})
         >
             Start New Game
         </button>
@@ -29,3 +30,8 @@ pub fn Lobby() -> impl IntoView {
         </A>
     }
 }
+
+#[server(CreateRoom, "/api")]
+pub async fn create_room() -> Result<RoomCode, ServerFnError> {
+    Ok(crate::server::sync::create_room())
+}
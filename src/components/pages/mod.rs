@@ -1,4 +1,4 @@
 mod lobby;
 mod room;
 pub use lobby::Lobby;
-pub use room::Room;
+pub use room::{room_exists, Room};
@@ -1,4 +1,5 @@
 use crate::components::game::Game;
+use crate::types::RoomCode;
 use ::leptos::*;
 use ::leptos_router::*;
 use ::std::time::Duration;
@@ -6,24 +7,32 @@ use ::std::time::Duration;
 #[component]
 pub fn Room() -> impl IntoView {
     let params = use_params_map();
-    let stale_code = String::from("stale");
+    let code = move || params.with(|p| p.get("code").cloned().unwrap_or_default());
+    let exists = create_resource(code, room_exists);
 
     // room code is part of the url.
     // if the room code doesn't match an active game redirect back to the landing page
     view! {
-        <Show
-            fallback=Game
-            when=move|| params.with(|p| p.get("code") == Option::Some(&stale_code))
-        >
-            <RedirectAfter
-                fallback= || "The room you're looking for doesn't exist. Maybe the game ended?  Redirecting to lobby..."
-                timeout=Duration::new(3,0)
-                path="/lobby"
-            />
-        </Show>
+        <Suspense fallback=|| ()>
+            {move || exists.get().map(|exists| match exists {
+                Ok(true) => view! { <Game room_code=code() /> },
+                _ => view! {
+                    <RedirectAfter
+                        fallback= || "The room you're looking for doesn't exist. Maybe the game ended?  Redirecting to lobby..."
+                        timeout=Duration::new(3,0)
+                        path="/lobby"
+                    />
+                },
+            })}
+        </Suspense>
     }
 }
 
+#[server(RoomExists, "/api")]
+pub async fn room_exists(code: RoomCode) -> Result<bool, ServerFnError> {
+    Ok(crate::server::sync::room(&code).is_some())
+}
+
 #[component]
 #[allow(unused_variables)]
 fn RedirectAfter<F, V>(fallback: F, path: &'static str, timeout: Duration) -> impl IntoView
@@ -1 +1,16 @@
 pub const DEV_MODE: bool = cfg!(feature = "dev");
+
+/// Fewest players a game can start with, so there's a judge and submissions to pick from
+pub const MIN_PLAYERS: usize = 3;
+
+/// Most players a single game can have. The audience is unlimited.
+pub const MAX_PLAYERS: usize = 16;
+
+/// Longest word allowed in a submission, in characters
+pub const MAX_WORD_LENGTH: usize = 32;
+
+/// Longest prompt template a player can write, in characters
+pub const MAX_PROMPT_LENGTH: usize = 200;
+
+/// Most prompts each player can write for a game
+pub const MAX_PROMPTS_PER_PLAYER: usize = 10;
@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+/// Which letters acronyms are drawn from, and how likely each letter is.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub enum LetterDistribution {
+    /// Relative frequency of each letter as the first letter of an English word
+    #[default]
+    English,
+    /// Every letter of the English alphabet is equally likely
+    Uniform,
+    /// English, without the letters that are a pain to come up with words for
+    NoRareLetters,
+    Spanish,
+    German,
+    /// Letters & weights picked by the host
+    Custom(Vec<(char, usize)>),
+}
+
+impl LetterDistribution {
+    /// Every profile, except custom, in the order they are listed at game setup
+    pub const PRESETS: [LetterDistribution; 5] = [
+        LetterDistribution::English,
+        LetterDistribution::Uniform,
+        LetterDistribution::NoRareLetters,
+        LetterDistribution::Spanish,
+        LetterDistribution::German,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LetterDistribution::English => "English",
+            LetterDistribution::Uniform => "Uniform",
+            LetterDistribution::NoRareLetters => "No Q, X or Z",
+            LetterDistribution::Spanish => "Spanish",
+            LetterDistribution::German => "German",
+            LetterDistribution::Custom(_) => "Custom",
+        }
+    }
+
+    /// Pairs of letters, with their relative weights.
+    /// Letters are always uppercase.
+    pub fn weights(&self) -> Cow<'_, [(char, usize)]> {
+        match self {
+            LetterDistribution::English => Cow::Borrowed(&ENGLISH),
+            LetterDistribution::Uniform => Cow::Owned(('A'..='Z').map(|c| (c, 1)).collect()),
+            LetterDistribution::NoRareLetters => Cow::Owned(
+                ENGLISH
+                    .into_iter()
+                    .filter(|(c, _)| !matches!(c, 'Q' | 'X' | 'Z'))
+                    .collect(),
+            ),
+            LetterDistribution::Spanish => Cow::Borrowed(&SPANISH),
+            LetterDistribution::German => Cow::Borrowed(&GERMAN),
+            LetterDistribution::Custom(weights) => Cow::Borrowed(weights),
+        }
+    }
+
+    /// Heaviest weight a custom letter can have
+    pub const MAX_WEIGHT: usize = 10_000;
+
+    /// Letters the host can use in a custom distribution:
+    /// A-Z, and the letters from the other languages' presets (like Ñ)
+    pub fn is_allowed_letter(letter: char) -> bool {
+        letter.is_ascii_uppercase()
+            || Self::PRESETS
+                .iter()
+                .any(|d| d.weights().iter().any(|(c, _)| *c == letter))
+    }
+
+    /// Custom weights need at least one letter that can be drawn, and every letter just once.
+    /// The error is a reason that can be shown to the host.
+    pub fn validate(&self) -> Result<(), String> {
+        let LetterDistribution::Custom(weights) = self else {
+            return Ok(());
+        };
+        if let Some((c, _)) = weights.iter().find(|(c, _)| !Self::is_allowed_letter(*c)) {
+            return Err(format!("{c:?} can't be used as a letter"));
+        }
+        let mut seen = HashSet::new();
+        if let Some((c, _)) = weights.iter().find(|(c, _)| !seen.insert(*c)) {
+            return Err(format!("{c} is listed more than once"));
+        }
+        if weights.iter().any(|(_, weight)| *weight > Self::MAX_WEIGHT) {
+            return Err(format!(
+                "Letter weights can be at most {}",
+                Self::MAX_WEIGHT
+            ));
+        }
+        if weights.iter().all(|(_, weight)| *weight == 0) {
+            return Err("At least one letter needs a weight above 0".to_owned());
+        }
+        Ok(())
+    }
+
+    /// Whether the letter can ever be drawn
+    pub fn contains(&self, letter: char) -> bool {
+        self.weights()
+            .iter()
+            .any(|(c, weight)| *weight > 0 && c.to_uppercase().eq(letter.to_uppercase()))
+    }
+}
+
+/// Based on a
+/// [Wikipedia](https://en.wikipedia.org/wiki/Letter_frequency#Relative_frequencies_of_the_first_letters_of_a_word_in_English_language).
+/// table of the relative frequency of each letter as the first letter in a word.
+/// I'v copied those values, multiplying every percentage by 100 to get whole integers.
+const ENGLISH: [(char, usize); 26] = [
+    ('S', 1100),
+    ('C', 940),
+    ('P', 770),
+    ('D', 610),
+    ('B', 600),
+    ('R', 600),
+    ('A', 570),
+    ('M', 560),
+    ('T', 500),
+    ('F', 410),
+    ('E', 390),
+    ('I', 390),
+    ('H', 370),
+    ('G', 330),
+    ('L', 310),
+    ('U', 290),
+    ('W', 270),
+    ('O', 250),
+    ('N', 220),
+    ('V', 150),
+    ('J', 110),
+    ('K', 100),
+    ('Q', 49),
+    ('Y', 36),
+    ('Z', 24),
+    ('X', 5),
+];
+
+/// Based on a
+/// [Wikipedia](https://en.wikipedia.org/wiki/Letter_frequency#Relative_frequencies_of_letters_in_other_languages)
+/// table of letter frequencies in Spanish.
+/// There is no first letter table for other languages, so these are overall frequencies.
+/// Percentages are multiplied by 100 and rounded, and accented vowels are left out.
+const SPANISH: [(char, usize); 27] = [
+    ('E', 1218),
+    ('A', 1153),
+    ('O', 868),
+    ('S', 798),
+    ('R', 687),
+    ('N', 671),
+    ('I', 625),
+    ('D', 501),
+    ('L', 497),
+    ('T', 463),
+    ('C', 402),
+    ('M', 316),
+    ('U', 293),
+    ('P', 251),
+    ('B', 222),
+    ('G', 177),
+    ('V', 114),
+    ('Y', 101),
+    ('Q', 88),
+    ('H', 70),
+    ('F', 69),
+    ('J', 49),
+    ('Z', 47),
+    ('Ñ', 31),
+    ('X', 22),
+    ('W', 2),
+    ('K', 1),
+];
+
+/// Based on a
+/// [Wikipedia](https://en.wikipedia.org/wiki/Letter_frequency#Relative_frequencies_of_letters_in_other_languages)
+/// table of letter frequencies in German.
+/// There is no first letter table for other languages, so these are overall frequencies.
+/// Percentages are multiplied by 100 and rounded.
+/// ß is left out, since words never start with it.
+const GERMAN: [(char, usize); 29] = [
+    ('E', 1640),
+    ('N', 978),
+    ('S', 727),
+    ('R', 700),
+    ('I', 655),
+    ('A', 652),
+    ('T', 615),
+    ('D', 508),
+    ('H', 458),
+    ('U', 417),
+    ('L', 344),
+    ('G', 301),
+    ('C', 273),
+    ('O', 259),
+    ('M', 253),
+    ('W', 192),
+    ('B', 189),
+    ('F', 166),
+    ('K', 142),
+    ('Z', 113),
+    ('Ü', 100),
+    ('V', 85),
+    ('P', 67),
+    ('Ä', 58),
+    ('Ö', 44),
+    ('J', 27),
+    ('Y', 4),
+    ('X', 3),
+    ('Q', 2),
+];
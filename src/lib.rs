@@ -22,8 +22,10 @@
 pub mod components;
 pub mod constants;
 pub mod extensions;
+pub mod letter_distribution;
 pub mod typed_context;
 pub mod types;
+pub mod validation;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "ssr")] {
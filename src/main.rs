@@ -2,13 +2,20 @@
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     use acronymia::components::app::App;
-    use acronymia::server::{sync, ws};
+    use acronymia::server::{prompt_packs, sync, ws};
     use actix_files::Files;
     use actix_web::{middleware, web, App, HttpServer};
     use leptos::get_configuration;
     use leptos_actix::{generate_route_list, LeptosRoutes};
 
-    sync::spawn_state_thread();
+    // `acronymia replay <log file> [events]` prints a game's state, instead of starting the server
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.first().is_some_and(|arg| arg == "replay") {
+        return acronymia::server::game_log::replay_command(&args[1..]);
+    }
+
+    prompt_packs::init();
+    sync::restore_rooms();
 
     // setting to `None` defaults to cargo-leptos & its env vars
     let conf = get_configuration(None).await.unwrap();
@@ -24,7 +31,7 @@ async fn main() -> std::io::Result<()> {
         let routes = &routes;
 
         App::new()
-            .service(web::resource("/ws").route(web::get().to(ws::handle_ws_request)))
+            .service(web::resource("/ws/{code}").route(web::get().to(ws::handle_ws_request)))
             .route("/api/{tail:.*}", leptos_actix::handle_server_fns())
             .leptos_routes(leptos_options.to_owned(), routes.to_owned(), App)
             .service(Files::new("/", site_root))
@@ -0,0 +1,97 @@
+//! Players run by the server, so small groups still have enough people for a game.
+//! Bots answer with random words from a bundled list, and pick winners at random.
+use super::types::*;
+use crate::validation::validate_word;
+use ::rand::prelude::*;
+use ::std::collections::HashMap;
+use ::std::sync::LazyLock;
+use ::std::time::Duration;
+
+/// How long bots wait before submitting, judging or voting, so they don't beat everyone to it
+pub const THINKING_TIME: Duration = Duration::from_secs(3);
+
+/// One word per line
+const WORDS: &str = include_str!("../../assets/bots/words.txt");
+
+/// The bundled words, by their first letter in uppercase
+static WORDS_BY_LETTER: LazyLock<HashMap<String, Vec<&'static str>>> = LazyLock::new(|| {
+    let mut words: HashMap<_, Vec<_>> = HashMap::new();
+    for word in WORDS.lines().map(str::trim).filter(|w| !w.is_empty()) {
+        if let Some(lead) = word.chars().next() {
+            words
+                .entry(lead.to_uppercase().collect())
+                .or_default()
+                .push(word);
+        }
+    }
+    words
+});
+
+/// A random word for each letter of the acronym.
+/// Letters stand in for a word by themselves when the word list has nothing for them,
+/// or the word it picked isn't allowed by the config.
+pub fn submission(acronym: &str, config: &Config, rng: &mut impl Rng) -> Submission {
+    acronym
+        .chars()
+        .map(|lead| {
+            let letter = lead.to_uppercase().collect::<String>();
+            WORDS_BY_LETTER
+                .get(&letter)
+                .and_then(|words| words.choose(rng))
+                .filter(|word| validate_word(lead, word, config).is_ok())
+                .map_or(letter, |word| (*word).to_owned())
+        })
+        .collect()
+}
+
+/// The first "Bot N" name that nobody in the room is using
+pub fn name(state: &GameState) -> String {
+    let mut n = 1;
+    loop {
+        let name = format!("Bot {n}");
+        if !state.is_name_taken(&PlayerId::new(), &name) {
+            return name;
+        }
+        n += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::{validate_submission, AlphabetPolicy};
+
+    #[test]
+    fn submissions_fit_the_acronym() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut config = Config {
+            alphabet: AlphabetPolicy::LettersOnly,
+            ..Config::default()
+        };
+        for acronym in ["ABCDEFGHIJ", "KLMNOPQRST", "UVWXYZ"] {
+            let submission = submission(acronym, &config, &mut rng);
+            assert_eq!(validate_submission(&submission, acronym, &config), Ok(()));
+            // every english letter has some words
+            assert!(
+                submission.iter().all(|word| word.len() > 1),
+                "{submission:?}"
+            );
+        }
+
+        config.letter_distribution = LetterDistribution::Spanish;
+        let spanish = submission("ÑA", &config, &mut rng);
+        assert_eq!(validate_submission(&spanish, "ÑA", &config), Ok(()));
+
+        // the only words for Ö have letters spanish doesn't use
+        let german = submission("ÖA", &config, &mut rng);
+        assert_eq!(german[0], "Ö");
+    }
+
+    #[test]
+    fn bots_get_unused_names() {
+        let mut state = demo_init(vec!["Bot 1", "Bot 3"]);
+        assert_eq!(name(&state), "Bot 2");
+        state.players.values_mut().for_each(|p| p.quit = true);
+        assert_eq!(name(&state), "Bot 1");
+    }
+}
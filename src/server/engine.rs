@@ -0,0 +1,1536 @@
+//! The game rules, without any I/O.
+//! Each event updates the game state, and returns the effects the room's driver should carry out.
+//! The driver owns the sockets & the clock, see `sync::spawn_state_thread`.
+use super::bots;
+use super::permissions::authorize;
+use super::prompt_packs;
+use super::random::uuid;
+use super::types::*;
+use crate::constants::{MAX_PLAYERS, MAX_PROMPTS_PER_PLAYER, MAX_PROMPT_LENGTH, MIN_PLAYERS};
+use ::leptos::logging::log;
+use ::rand::prelude::*;
+use ::std::collections::*;
+use ::std::time::{Duration, Instant};
+
+/// Something that happened to the game
+#[derive(Clone, Debug)]
+pub enum Event {
+    /// Sent by the client on the other end of a socket
+    Message(SessionId, ClientMessage),
+    /// The timer armed in this generation ran out
+    Timeout(u64),
+    /// The player went offline at this time, and the grace period has passed
+    GraceExpired(PlayerId, Instant),
+    /// Bots take their turn, if the timer armed in this generation is still running
+    BotsPlay(u64),
+}
+
+/// Something the driver should do, after an event is applied
+#[derive(Clone, Debug)]
+pub enum Effect {
+    /// Send to every socket in the room
+    Broadcast(ServerMessage),
+    /// Send to a single socket
+    Reply(SessionId, ServerMessage),
+    /// Apply the event once the duration has passed
+    Schedule(Duration, Event),
+    /// The game state changed, so it's worth saving a snapshot.
+    /// Always the last effect, and only once per event.
+    Save,
+}
+
+/// Collects the effects of a single event
+pub struct Turn {
+    /// When the event happened
+    now: Instant,
+    effects: Vec<Effect>,
+    /// Whether the event changed the game state
+    changed: bool,
+}
+
+impl Turn {
+    fn new(now: Instant) -> Self {
+        Turn {
+            now,
+            effects: Vec::new(),
+            changed: false,
+        }
+    }
+
+    /// Call whenever the game state changes.
+    /// Anything that sends a patch already does.
+    fn changed(&mut self) {
+        self.changed = true;
+    }
+
+    fn finish(mut self) -> Vec<Effect> {
+        if self.changed {
+            self.effects.push(Effect::Save);
+        }
+        self.effects
+    }
+
+    fn broadcast(&mut self, message: ServerMessage) {
+        self.effects.push(Effect::Broadcast(message));
+    }
+
+    fn reply(&mut self, session_id: &SessionId, message: ServerMessage) {
+        self.effects
+            .push(Effect::Reply(session_id.clone(), message));
+    }
+
+    fn schedule(&mut self, delay: Duration, event: Event) {
+        self.effects.push(Effect::Schedule(delay, event));
+    }
+}
+
+/// Applies an event to the game.
+/// `now` comes from the driver, so the same events always have the same outcome.
+pub fn apply(
+    state: &mut GameState,
+    sessions: &mut Sessions,
+    event: Event,
+    now: Instant,
+) -> Vec<Effect> {
+    let mut turn = Turn::new(now);
+    match event {
+        Event::Message(session_id, message) => {
+            handle_message(&session_id, message, state, sessions, &mut turn);
+        }
+        Event::Timeout(generation) => on_timeout(generation, state, &mut turn),
+        Event::GraceExpired(id, offline_since) => {
+            // they haven't reconnected, or disconnected again, since
+            let gone = state
+                .players
+                .get(&id)
+                .is_some_and(|p| p.offline_since == Some(offline_since));
+            if gone {
+                log!("player {} didn't reconnect in time", id);
+                quit_player(&id, state, &mut turn);
+            }
+        }
+        Event::BotsPlay(generation) => bots_play(generation, state, &mut turn),
+    }
+    turn.finish()
+}
+
+/// Every message is checked against the permission table before it's handled.
+#[allow(clippy::too_many_lines)]
+fn handle_message(
+    session_id: &SessionId,
+    message: ClientMessage,
+    state: &mut GameState,
+    sessions: &mut Sessions,
+    turn: &mut Turn,
+) {
+    log!("session {:#?}", session_id);
+    let in_reply_to = message.name();
+    if let Err(denied) = authorize(&message, sessions.player_id(session_id), state) {
+        let rejection = (denied.code(), denied.to_string());
+        reply_error(session_id, turn, in_reply_to, rejection);
+        return;
+    }
+
+    match message {
+        ClientMessage::Connect(player_id) => {
+            match sessions.connect(session_id.clone(), player_id.clone()) {
+                Ok(()) => {
+                    let patches = turn.effects.len();
+                    go_online(&player_id, state, turn);
+                    // the host left while nobody else was around to take over
+                    let host_left = !state.host.as_ref().is_some_and(|h| state.is_player(h));
+                    if host_left && state.is_player(&player_id) {
+                        change_host(player_id, state, turn);
+                    }
+                    // broadcast patches get applied on top of this snapshot,
+                    // so it goes out first. It already includes the patches from connecting.
+                    send_game_state(session_id, state, sessions, turn);
+                    turn.effects[patches..].rotate_right(1);
+                }
+                Err(_) => {
+                    let rejection = (
+                        ErrorCode::DuplicateSession,
+                        "You're already connected to this game, from another tab or device"
+                            .to_owned(),
+                    );
+                    reply_error(session_id, turn, in_reply_to, rejection);
+                }
+            }
+        }
+
+        ClientMessage::Disconnect => {
+            let player_id = sessions.player_id(session_id).cloned();
+            sessions.remove(session_id);
+            if let Some(id) = player_id
+                && !sessions.is_connected(&id)
+            {
+                go_offline(id, state, turn);
+            }
+        }
+
+        // register your name for the current game
+        // allows you to update your name if you already joined
+        ClientMessage::JoinGame { name } => {
+            let Some(id) = sessions.player_id(session_id).cloned() else {
+                return;
+            };
+
+            if state.is_name_taken(&id, &name) {
+                let rejection = (ErrorCode::NameTaken, format!("{name} is already taken"));
+                reply_error(session_id, turn, in_reply_to, rejection);
+                return;
+            }
+
+            // late joiners get to watch
+            if state.step != GameStep::Setup {
+                if !state.is_player(&id) {
+                    join_audience(id, name, state, turn);
+                    let hint = "The game already started, so you've joined the audience.";
+                    turn.reply(session_id, ServerMessage::Hint(hint.to_owned()));
+                }
+                return;
+            }
+
+            let active_players = state.rotation.iter().filter(|id| state.is_player(id));
+            if !state.is_player(&id) && active_players.count() >= MAX_PLAYERS {
+                let rejection = (
+                    ErrorCode::RoomFull,
+                    format!("The game is full, at {MAX_PLAYERS} players. You can still watch!"),
+                );
+                reply_error(session_id, turn, in_reply_to, rejection);
+                return;
+            }
+
+            // joining the game takes you out of the audience
+            state.audience.retain(|p| p.id != id);
+            let server_player = ServerPlayer {
+                id: id.clone(),
+                name: name.clone(),
+                quit: false,
+                connected: true,
+                offline_since: None,
+                bot: false,
+            };
+            if state.players.insert(id.clone(), server_player).is_none() {
+                state.rotation.push(id.clone());
+            }
+
+            let player = Player {
+                id: id.clone(),
+                name,
+            };
+            send_patch(StatePatch::PlayerJoined(player), state, turn);
+            if state.host.is_none() {
+                change_host(id, state, turn);
+            }
+        }
+
+        ClientMessage::JoinAudience { name } => {
+            if let Some(id) = sessions.player_id(session_id) {
+                if state.is_name_taken(id, &name) {
+                    let rejection = (ErrorCode::NameTaken, format!("{name} is already taken"));
+                    reply_error(session_id, turn, in_reply_to, rejection);
+                    return;
+                }
+
+                // players can't also be spectators
+                if !state.is_player(id) {
+                    join_audience(id.clone(), name, state, turn);
+                }
+            }
+        }
+
+        ClientMessage::KickPlayer(id) => {
+            quit_player(&id, state, turn);
+        }
+
+        ClientMessage::TransferHost(id) => {
+            if !state.is_player(&id) || state.is_bot(&id) {
+                let rejection = (
+                    ErrorCode::NotAllowed,
+                    "Only players in the game can be host, and bots can't".to_owned(),
+                );
+                reply_error(session_id, turn, in_reply_to, rejection);
+                return;
+            }
+            change_host(id, state, turn);
+        }
+
+        ClientMessage::AddBot => {
+            if state.active_players().count() >= MAX_PLAYERS {
+                let rejection = (
+                    ErrorCode::RoomFull,
+                    format!("The game is full, at {MAX_PLAYERS} players"),
+                );
+                reply_error(session_id, turn, in_reply_to, rejection);
+                return;
+            }
+
+            let bot = Player {
+                id: uuid(&mut state.rng),
+                name: bots::name(state),
+            };
+            let server_player = ServerPlayer {
+                id: bot.id.clone(),
+                name: bot.name.clone(),
+                quit: false,
+                // bots never go offline
+                connected: true,
+                offline_since: None,
+                bot: true,
+            };
+            state.players.insert(bot.id.clone(), server_player);
+            state.rotation.push(bot.id.clone());
+            send_patch(StatePatch::BotJoined(bot), state, turn);
+        }
+
+        ClientMessage::UpdateConfig(config) => {
+            let valid = config
+                .validate()
+                .and_then(|()| prompt_packs::check_ids(&config.prompt_packs));
+            if let Err(reason) = valid {
+                reply_error(
+                    session_id,
+                    turn,
+                    in_reply_to,
+                    (ErrorCode::InvalidConfig, reason),
+                );
+                // undo the host's edit
+                send_game_state(session_id, state, sessions, turn);
+                return;
+            }
+            if config != state.config {
+                let patch = StatePatch::ConfigChanged(config.clone());
+                state.config = config;
+                send_patch(patch, state, turn);
+            }
+        }
+
+        ClientMessage::StartGame => {
+            if state.active_players().count() < MIN_PLAYERS {
+                let rejection = (
+                    ErrorCode::NotAllowed,
+                    format!("The game needs at least {MIN_PLAYERS} players"),
+                );
+                reply_error(session_id, turn, in_reply_to, rejection);
+                return;
+            }
+            log!("game started with seed {}", state.seed);
+            state.prompts = prompt_packs::deck(&state.config.prompt_packs, &mut state.rng);
+            state.deal_player_prompts();
+            start_submission_step(state, turn);
+        }
+
+        ClientMessage::SubmitPrompt(template) => {
+            let Some(author) = sessions.player_id(session_id) else {
+                return;
+            };
+
+            let written = state
+                .player_prompts
+                .iter()
+                .filter(|p| p.author == *author)
+                .count();
+            if written >= MAX_PROMPTS_PER_PLAYER {
+                let rejection = (
+                    ErrorCode::NotAllowed,
+                    format!("You can write at most {MAX_PROMPTS_PER_PLAYER} prompts"),
+                );
+                reply_error(session_id, turn, in_reply_to, rejection);
+                return;
+            }
+
+            if template.chars().count() > MAX_PROMPT_LENGTH {
+                let rejection = (
+                    ErrorCode::InvalidPrompt,
+                    format!("Prompts can be at most {MAX_PROMPT_LENGTH} characters"),
+                );
+                reply_error(session_id, turn, in_reply_to, rejection);
+                return;
+            }
+
+            let Some((before, after)) = parse_prompt(&template) else {
+                let rejection = (
+                    ErrorCode::InvalidPrompt,
+                    "Prompts need exactly one blank, written as ___".to_owned(),
+                );
+                reply_error(session_id, turn, in_reply_to, rejection);
+                return;
+            };
+
+            let prompt = PlayerPrompt {
+                id: uuid(&mut state.rng),
+                author: author.clone(),
+                before,
+                after,
+            };
+            state.player_prompts.push(prompt.clone());
+            send_patch(StatePatch::PromptAdded(prompt), state, turn);
+        }
+
+        ClientMessage::RemovePrompt(id) => {
+            let Some(player_id) = sessions.player_id(session_id) else {
+                return;
+            };
+
+            let Some(prompt) = state.player_prompts.iter().find(|p| p.id == id) else {
+                let rejection = (
+                    ErrorCode::InvalidPrompt,
+                    "That prompt was already removed".to_owned(),
+                );
+                reply_error(session_id, turn, in_reply_to, rejection);
+                return;
+            };
+
+            // only the author or the host can remove a prompt
+            if !state.is_host(player_id) && prompt.author != *player_id {
+                let rejection = (
+                    ErrorCode::NotAllowed,
+                    "Only the author or the host can remove a prompt".to_owned(),
+                );
+                reply_error(session_id, turn, in_reply_to, rejection);
+                return;
+            }
+
+            state.player_prompts.retain(|p| p.id != id);
+            send_patch(StatePatch::PromptRemoved(id), state, turn);
+        }
+
+        ClientMessage::SubmitAcronym(submission) => {
+            let Some(id) = sessions.player_id(session_id).cloned() else {
+                return;
+            };
+
+            let acronym = state.rounds.last().map(|r| &r.prompt.acronym);
+            let valid = acronym.map(|a| validate_submission(&submission, a, &state.config));
+            if let Some(Err(reason)) = valid {
+                let rejection = (ErrorCode::InvalidSubmission, reason);
+                reply_error(session_id, turn, in_reply_to, rejection);
+                return;
+            }
+
+            let hint = match state.penalty(&submission) {
+                0 => "Submission received.".to_owned(),
+                penalty => format!(
+                    "Submission received. It will lose {} points for characters that aren't letters.",
+                    -penalty
+                ),
+            };
+            turn.reply(session_id, ServerMessage::Hint(hint));
+
+            let Some(round) = state.rounds.last_mut() else {
+                return;
+            };
+            let token = round.token(&id);
+            turn.reply(session_id, ServerMessage::OwnSubmission(token));
+            add_submission(id, submission, state, turn);
+        }
+
+        ClientMessage::JudgeRound(token) => {
+            if let Err(rejection) = pick_winner(&token, state, turn) {
+                reply_error(session_id, turn, in_reply_to, rejection);
+            }
+        }
+
+        ClientMessage::CastVote(token) => {
+            let Some(voter) = sessions.player_id(session_id) else {
+                return;
+            };
+            if let Err(rejection) = cast_vote(voter.clone(), &token, state, turn) {
+                reply_error(session_id, turn, in_reply_to, rejection);
+            }
+        }
+
+        ClientMessage::AudienceVote(token) => {
+            let Some(voter) = sessions.player_id(session_id) else {
+                return;
+            };
+            if let Err(rejection) = cast_audience_vote(voter.clone(), &token, state, turn) {
+                reply_error(session_id, turn, in_reply_to, rejection);
+            }
+        }
+
+        ClientMessage::Resync => {
+            send_game_state(session_id, state, sessions, turn);
+        }
+
+        ClientMessage::GetRemainingTime => {
+            let remaining = state.timer.remaining_secs(turn.now);
+            turn.reply(session_id, ServerMessage::UpdateRemainingTime(remaining));
+        }
+
+        // BEGIN DEBUG MESSAGES
+        ClientMessage::ResetState => {
+            // timeouts that are already scheduled belong to the old game
+            let generation = state.timer_generation + 1;
+            // drawn from the old game, so replays reset to the same game
+            let seed = state.rng.next_u64();
+            *state = game_state_init();
+            state.timer_generation = generation;
+            state.reseed(seed);
+            let client_state = state.to_client_state(None, turn.now);
+            turn.broadcast(ServerMessage::GameState(client_state));
+            turn.changed();
+        }
+
+        ClientMessage::StopTimer => {
+            state.cancel_timer();
+            turn.changed();
+        } // END DEBUG MESSAGES
+    }
+}
+
+/// Sends the whole client game state to a single socket.
+/// Only needed when someone connects or falls out of sync, otherwise prefer `send_patch`.
+fn send_game_state(
+    session_id: &SessionId,
+    state: &GameState,
+    sessions: &Sessions,
+    turn: &mut Turn,
+) {
+    let viewer = sessions.player_id(session_id);
+    let message = ServerMessage::GameState(state.to_client_state(viewer, turn.now));
+    turn.reply(session_id, message);
+}
+
+/// Why a message was rejected, before it's sent back to the client
+type Rejection = (ErrorCode, String);
+
+fn reply_error(
+    session_id: &SessionId,
+    turn: &mut Turn,
+    in_reply_to: &str,
+    (code, message): Rejection,
+) {
+    let error = ServerMessage::Error {
+        code,
+        message,
+        in_reply_to: Some(in_reply_to.to_owned()),
+    };
+    turn.reply(session_id, error);
+}
+
+/// Sends an incremental update to everyone in the room, bumping the version.
+fn send_patch(patch: StatePatch, state: &mut GameState, turn: &mut Turn) {
+    turn.changed();
+    state.version += 1;
+    turn.broadcast(ServerMessage::Patch {
+        version: state.version,
+        patch,
+    });
+}
+
+fn unknown_submission() -> Rejection {
+    (
+        ErrorCode::InvalidSubmission,
+        "That submission isn't part of this round".to_owned(),
+    )
+}
+
+/// Call once the author has a token for this round
+fn add_submission(id: PlayerId, submission: Submission, state: &mut GameState, turn: &mut Turn) {
+    let Some(round) = state.rounds.last_mut() else {
+        return;
+    };
+    let prev = round.submissions.insert(id, submission);
+    turn.changed();
+
+    // if all submissions are in, go to judging step
+    if state.all_submitted() {
+        start_judging_step(state, turn);
+    } else if prev.is_none() {
+        send_patch(StatePatch::IncrementSubmissionCount, state, turn);
+    }
+}
+
+fn pick_winner(token: &str, state: &mut GameState, turn: &mut Turn) -> Result<(), Rejection> {
+    let judge = state.acting_judge();
+    let Some(round) = state.rounds.last_mut() else {
+        return Ok(());
+    };
+    // prevent double submission
+    if round.winner.is_some() {
+        return Err((
+            ErrorCode::NotAllowed,
+            "The winner was already picked".to_owned(),
+        ));
+    }
+    let Some(winner_id) = round.author_of(token).cloned() else {
+        return Err(unknown_submission());
+    };
+    // the host took over judging after the submissions were revealed
+    if judge.as_ref() == Some(&winner_id) {
+        return Err((
+            ErrorCode::InvalidSubmission,
+            "You can't pick your own submission".to_owned(),
+        ));
+    }
+    round.winner = Some(winner_id.clone());
+
+    set_timer(TimerTag::ShowRoundWinner, state, turn);
+    let patch = StatePatch::ShowRoundWinner {
+        winner: winner_id,
+        authors: state.revealed_authors(),
+    };
+    send_patch(patch, state, turn);
+    Ok(())
+}
+
+fn cast_vote(
+    voter: PlayerId,
+    token: &str,
+    state: &mut GameState,
+    turn: &mut Turn,
+) -> Result<(), Rejection> {
+    let Some(round) = state.rounds.last_mut() else {
+        return Ok(());
+    };
+
+    // votes are locked in once the results are shown
+    if round.votes_closed {
+        return Err((ErrorCode::WrongStep, "Voting has closed".to_owned()));
+    }
+
+    let Some(candidate) = round.author_of(token).cloned() else {
+        return Err(unknown_submission());
+    };
+
+    // no voting for yourself
+    if voter == candidate {
+        return Err((
+            ErrorCode::InvalidSubmission,
+            "You can't vote for yourself".to_owned(),
+        ));
+    }
+
+    let prev = round.votes.insert(voter, candidate);
+    turn.changed();
+
+    // if all votes are in, reveal the results
+    if state.all_voted() {
+        end_voting_step(state, turn);
+    } else if prev.is_none() {
+        send_patch(StatePatch::IncrementVoteCount, state, turn);
+    }
+    Ok(())
+}
+
+fn change_host(id: PlayerId, state: &mut GameState, turn: &mut Turn) {
+    state.host = Some(id.clone());
+    send_patch(StatePatch::HostChanged(id), state, turn);
+    // the host fills in for a judge who left
+    withdraw_judge_submission(state, turn);
+}
+
+/// Whoever takes over judging partway through a round can't judge their own submission,
+/// so it's dropped. Call after anything that can change the acting judge.
+fn withdraw_judge_submission(state: &mut GameState, turn: &mut Turn) {
+    if state.is_head_to_head() || state.step != GameStep::Submission {
+        return;
+    }
+    let Some(judge) = state.acting_judge() else {
+        return;
+    };
+    let Some(round) = state.rounds.last_mut() else {
+        return;
+    };
+    if round.submissions.remove(&judge).is_some() {
+        round.tokens.remove(&judge);
+        turn.changed();
+        send_patch(StatePatch::SubmissionWithdrawn, state, turn);
+    }
+}
+
+/// Passes the host role on to the next connected player.
+/// If nobody else is connected, whoever connects next takes over.
+fn hand_off_host(state: &mut GameState, turn: &mut Turn) {
+    if let Some(next) = state.next_host() {
+        change_host(next, state, turn);
+    }
+}
+
+fn go_online(id: &PlayerId, state: &mut GameState, turn: &mut Turn) {
+    let Some(player) = state.players.get_mut(id) else {
+        return;
+    };
+    player.offline_since = None;
+    if !player.connected {
+        player.connected = true;
+        let patch = StatePatch::ConnectionChanged {
+            id: id.clone(),
+            connected: true,
+        };
+        send_patch(patch, state, turn);
+    }
+}
+
+/// Starts the grace period, after which the player quits unless they've reconnected
+fn go_offline(id: PlayerId, state: &mut GameState, turn: &mut Turn) {
+    let Some(player) = state.players.get_mut(&id) else {
+        return;
+    };
+    if player.quit {
+        return;
+    }
+    player.connected = false;
+    player.offline_since = Some(turn.now);
+    let patch = StatePatch::ConnectionChanged {
+        id: id.clone(),
+        connected: false,
+    };
+    send_patch(patch, state, turn);
+
+    let grace = Duration::from_secs(state.config.timer_secs.reconnect_grace);
+    turn.schedule(grace, Event::GraceExpired(id, turn.now));
+}
+
+/// Takes a player out of the game.
+/// They stay in the rotation, so the judge order doesn't change, and their score is still shown.
+fn quit_player(id: &PlayerId, state: &mut GameState, turn: &mut Turn) {
+    let Some(player) = state.players.get_mut(id) else {
+        return;
+    };
+    if player.quit {
+        return;
+    }
+    player.quit = true;
+    player.offline_since = None;
+    send_patch(StatePatch::PlayerKicked(id.clone()), state, turn);
+
+    if state.is_host(id) {
+        hand_off_host(state, turn);
+    }
+    withdraw_judge_submission(state, turn);
+
+    // don't keep everyone waiting on someone who isn't coming back
+    match state.step {
+        GameStep::Submission if state.all_submitted() => start_judging_step(state, turn),
+        GameStep::Voting
+            if state.all_voted() && !state.rounds.last().is_some_and(|r| r.votes_closed) =>
+        {
+            end_voting_step(state, turn);
+        }
+        _ => {}
+    }
+}
+
+/// Adds a spectator, or updates their name if they are already watching
+fn join_audience(id: PlayerId, name: String, state: &mut GameState, turn: &mut Turn) {
+    let spectator = Player { id, name };
+    if let Some(p) = state.audience.iter_mut().find(|p| p.id == spectator.id) {
+        p.name.clone_from(&spectator.name);
+    } else {
+        state.audience.push(spectator.clone());
+    }
+    send_patch(StatePatch::AudienceJoined(spectator), state, turn);
+}
+
+fn cast_audience_vote(
+    voter: PlayerId,
+    token: &str,
+    state: &mut GameState,
+    turn: &mut Turn,
+) -> Result<(), Rejection> {
+    let Some(round) = state.rounds.last_mut() else {
+        return Ok(());
+    };
+
+    // voting ends once the winner is revealed
+    if round.winner.is_some() || round.votes_closed {
+        return Err((ErrorCode::WrongStep, "Voting has closed".to_owned()));
+    }
+
+    let Some(candidate) = round.author_of(token).cloned() else {
+        return Err(unknown_submission());
+    };
+
+    let prev = round.audience_votes.insert(voter, candidate);
+    turn.changed();
+    if prev.is_none() {
+        send_patch(StatePatch::IncrementAudienceVoteCount, state, turn);
+    }
+    Ok(())
+}
+
+fn start_submission_step(state: &mut GameState, turn: &mut Turn) {
+    state.cancel_timer();
+    let prompt = state.next_prompt();
+    state.rounds.push(Round {
+        judge: state.next_judge(),
+        winner: None,
+        submissions: HashMap::new(),
+        votes: HashMap::new(),
+        votes_closed: false,
+        audience_votes: HashMap::new(),
+        tokens: HashMap::new(),
+        prompt,
+    });
+
+    state.step = GameStep::Submission;
+    set_timer(TimerTag::Submission, state, turn);
+    wake_bots(state, turn);
+    let patch = StatePatch::RoundStarted {
+        judge: state.judge_id(),
+        prompt: state
+            .rounds
+            .last()
+            .map(|r| r.prompt.clone())
+            .unwrap_or_default(),
+        round_counter: state.round_counter(),
+        timer: state.timer.remaining_secs(turn.now),
+    };
+    send_patch(patch, state, turn);
+}
+
+fn start_judging_step(state: &mut GameState, turn: &mut Turn) {
+    if state.is_head_to_head() {
+        start_voting_step(state, turn);
+        return;
+    }
+
+    state.cancel_timer();
+    state.step = GameStep::Judging;
+    state.shuffle_current_round_submissions();
+
+    set_timer(TimerTag::Judging, state, turn);
+    wake_bots(state, turn);
+    reveal_submissions(state, turn);
+}
+
+fn start_voting_step(state: &mut GameState, turn: &mut Turn) {
+    state.cancel_timer();
+    state.step = GameStep::Voting;
+    state.shuffle_current_round_submissions();
+
+    set_timer(TimerTag::Voting, state, turn);
+    wake_bots(state, turn);
+    reveal_submissions(state, turn);
+}
+
+fn reveal_submissions(state: &mut GameState, turn: &mut Turn) {
+    let step = StatePatch::StepChanged {
+        step: state.step.clone(),
+        timer: state.timer.remaining_secs(turn.now),
+    };
+    send_patch(step, state, turn);
+    let submissions = StatePatch::SubmissionsRevealed(state.anonymous_submissions());
+    send_patch(submissions, state, turn);
+}
+
+/// Tallies the votes, and shows them off before moving on to the next round.
+fn end_voting_step(state: &mut GameState, turn: &mut Turn) {
+    let Some(round) = state.rounds.last_mut() else {
+        return;
+    };
+
+    // ties go to whoever was shown first
+    let tallies = round.vote_tallies();
+    let mut winner = None;
+    let mut most_votes = 0;
+    for (id, _) in &state.shuffled_submissions {
+        let votes = tallies.get(id).copied().unwrap_or(0);
+        if votes > most_votes {
+            most_votes = votes;
+            winner = Some(id.clone());
+        }
+    }
+    round.winner = winner;
+    round.votes_closed = true;
+
+    set_timer(TimerTag::ShowRoundWinner, state, turn);
+    let patch = StatePatch::VotesRevealed {
+        winner: state.rounds.last().and_then(|r| r.winner.clone()),
+        tallies: state.revealed_vote_tallies(),
+        authors: state.revealed_authors(),
+    };
+    send_patch(patch, state, turn);
+}
+
+fn end_judging_step(state: &mut GameState, turn: &mut Turn) {
+    // game end
+    if state.is_game_over() {
+        state.step = GameStep::Results;
+        let step = StatePatch::StepChanged {
+            step: GameStep::Results,
+            timer: None,
+        };
+        send_patch(step, state, turn);
+        let scores = StatePatch::ScoresPublished {
+            scores: state.scores(),
+            audience_bonuses: state.audience_bonuses_by_name(),
+        };
+        send_patch(scores, state, turn);
+    // next round
+    } else {
+        start_submission_step(state, turn);
+    }
+}
+
+fn set_timer(tag: TimerTag, state: &mut GameState, turn: &mut Turn) {
+    let duration = Timer::duration(&tag, &state.config);
+    arm_timer(tag, duration, state, turn);
+}
+
+/// Picks a game restored from a snapshot back up.
+/// Nobody has a socket open yet, so everyone gets the grace period to reconnect.
+/// The timer is re-armed with whatever time it had left.
+pub fn restore(
+    state: &mut GameState,
+    timer: Option<(TimerTag, Duration)>,
+    now: Instant,
+) -> Vec<Effect> {
+    let mut turn = Turn::new(now);
+    let players = state
+        .active_players()
+        .filter(|id| !state.is_bot(id))
+        .cloned()
+        .collect::<Vec<_>>();
+    for id in players {
+        go_offline(id, state, &mut turn);
+    }
+    if let Some((tag, remaining)) = timer {
+        arm_timer(tag, remaining, state, &mut turn);
+        // in case they hadn't had their turn yet
+        wake_bots(state, &mut turn);
+    }
+    turn.finish()
+}
+
+fn arm_timer(tag: TimerTag, duration: Duration, state: &mut GameState, turn: &mut Turn) {
+    turn.changed();
+    state.cancel_timer();
+    state.timer = Timer::new(turn.now, duration, tag);
+    turn.schedule(duration, Event::Timeout(state.timer_generation));
+}
+
+fn on_timeout(generation: u64, state: &mut GameState, turn: &mut Turn) {
+    // the timer was cancelled or replaced after this timeout was scheduled
+    if generation != state.timer_generation {
+        return;
+    }
+    match state.timer.tag().cloned() {
+        Some(TimerTag::Submission) => start_judging_step(state, turn),
+        Some(TimerTag::Voting) => end_voting_step(state, turn),
+        Some(TimerTag::Judging | TimerTag::ShowRoundWinner) => end_judging_step(state, turn),
+        None => {}
+    }
+}
+
+/// Gives the bots a moment, then has them take their turn at the current step.
+/// Call after the step's timer is set.
+fn wake_bots(state: &GameState, turn: &mut Turn) {
+    if state.active_bots().next().is_some() {
+        let event = Event::BotsPlay(state.timer_generation);
+        turn.schedule(bots::THINKING_TIME, event);
+    }
+}
+
+/// Bots that haven't had their turn yet submit, judge or vote
+fn bots_play(generation: u64, state: &mut GameState, turn: &mut Turn) {
+    // the step is over, or the timer was stopped
+    if generation != state.timer_generation {
+        return;
+    }
+    match state.timer.tag() {
+        Some(TimerTag::Submission) => bots_submit(state, turn),
+        Some(TimerTag::Judging) => bots_judge(state, turn),
+        Some(TimerTag::Voting) => bots_vote(state, turn),
+        Some(TimerTag::ShowRoundWinner) | None => {}
+    }
+}
+
+fn bots_submit(state: &mut GameState, turn: &mut Turn) {
+    let Some(round) = state.rounds.last() else {
+        return;
+    };
+    let judge = state.acting_judge();
+    let bots = state
+        .active_bots()
+        .filter(|id| Some(*id) != judge.as_ref() && !round.submissions.contains_key(*id))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    for id in bots {
+        // the last submission moves the game on
+        let Some(round) = state.rounds.last_mut() else {
+            return;
+        };
+        if state.step != GameStep::Submission {
+            return;
+        }
+        round.token(&id);
+        let submission = bots::submission(&round.prompt.acronym, &state.config, &mut state.rng);
+        add_submission(id, submission, state, turn);
+    }
+}
+
+fn bots_judge(state: &mut GameState, turn: &mut Turn) {
+    if !state.acting_judge().is_some_and(|id| state.is_bot(&id)) {
+        return;
+    }
+    let submissions = state.anonymous_submissions();
+    if let Some((token, _)) = submissions.choose(&mut state.rng) {
+        _ = pick_winner(token, state, turn);
+    }
+}
+
+fn bots_vote(state: &mut GameState, turn: &mut Turn) {
+    let Some(round) = state.rounds.last() else {
+        return;
+    };
+    let bots = state
+        .active_bots()
+        .filter(|id| !round.votes.contains_key(*id))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    for id in bots {
+        // the last vote closes voting
+        let Some(round) = state.rounds.last() else {
+            return;
+        };
+        if round.votes_closed {
+            return;
+        }
+        let own = round.tokens.get(&id);
+        let others = state
+            .anonymous_submissions()
+            .into_iter()
+            .map(|(token, _)| token)
+            .filter(|token| Some(token) != own)
+            .collect::<Vec<_>>();
+        if let Some(token) = others.choose(&mut state.rng) {
+            _ = cast_vote(id, token, state, turn);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::permissions::{authorize, Denied};
+    use crate::server::testing::*;
+
+    /// Three players, someone watching, and someone who connected without joining
+    fn at_step(step: &GameStep) -> Table {
+        let config = Config {
+            mode: if *step == GameStep::Voting {
+                GameMode::HeadToHead
+            } else {
+                GameMode::Judge
+            },
+            game_length: GameLength::Rounds(1),
+            ..Config::default()
+        };
+        let mut table = Table::new(&["a", "b", "c"], config);
+        table.send("x", ClientMessage::Connect("x".to_owned()));
+        let name = "x".to_owned();
+        table.send("x", ClientMessage::JoinAudience { name });
+        table.send("z", ClientMessage::Connect("z".to_owned()));
+
+        if *step != GameStep::Setup {
+            table.send("a", ClientMessage::StartGame);
+        }
+        if matches!(
+            step,
+            GameStep::Judging | GameStep::Voting | GameStep::Results
+        ) {
+            table.submit_all();
+        }
+        if *step == GameStep::Results {
+            let judge = table.state.judge_id().unwrap();
+            let token = table.state.anonymous_submissions()[0].0.clone();
+            table.send(&judge, ClientMessage::JudgeRound(token));
+            table.apply(Event::Timeout(table.state.timer_generation));
+        }
+        assert_eq!(table.state.step, *step);
+        table
+    }
+
+    /// One of each message, aimed at this round where that matters
+    fn every_message(state: &GameState) -> Vec<ClientMessage> {
+        let token = state
+            .rounds
+            .last()
+            .and_then(|r| r.tokens.values().next().cloned())
+            .unwrap_or_default();
+        vec![
+            ClientMessage::Connect("b".to_owned()),
+            ClientMessage::JoinGame {
+                name: "new name".to_owned(),
+            },
+            ClientMessage::JoinAudience {
+                name: "watcher".to_owned(),
+            },
+            ClientMessage::KickPlayer("c".to_owned()),
+            ClientMessage::TransferHost("b".to_owned()),
+            ClientMessage::AddBot,
+            ClientMessage::UpdateConfig(Config {
+                target_score: Some(5),
+                ..state.config.clone()
+            }),
+            ClientMessage::StartGame,
+            ClientMessage::SubmitPrompt("Why ___?".to_owned()),
+            ClientMessage::RemovePrompt(String::new()),
+            ClientMessage::SubmitAcronym(vec!["Nope".to_owned()]),
+            ClientMessage::JudgeRound(token.clone()),
+            ClientMessage::CastVote(token.clone()),
+            ClientMessage::AudienceVote(token),
+            ClientMessage::GetRemainingTime,
+            ClientMessage::Resync,
+            ClientMessage::ResetState,
+            ClientMessage::StopTimer,
+            ClientMessage::Disconnect,
+        ]
+    }
+
+    #[test]
+    fn every_message_in_every_step() {
+        let steps = [
+            GameStep::Setup,
+            GameStep::Submission,
+            GameStep::Judging,
+            GameStep::Voting,
+            GameStep::Results,
+        ];
+        // host, player, audience, connected but not joined, never connected
+        let senders = ["a", "b", "x", "z", "nobody"];
+
+        for step in &steps {
+            for sender in senders {
+                let session_id = session(sender);
+                for message in every_message(&at_step(step).state) {
+                    let mut table = at_step(step);
+                    let context = format!("{sender} sending {message:?} during {step:?}");
+                    let player_id = table.sessions.player_id(&session_id).cloned();
+                    let allowed = authorize(&message, player_id.as_ref(), &table.state);
+                    let name = message.name();
+                    let before = serde_json::to_string(&table.state).unwrap();
+                    let version = table.state.version;
+
+                    let effects = table.send(sender, message);
+
+                    if let Err(denied) = allowed {
+                        assert!(
+                            matches!(
+                                effects.as_slice(),
+                                [Effect::Reply(id, ServerMessage::Error { code, in_reply_to, .. })]
+                                    if *id == session_id
+                                        && *code == denied.code()
+                                        && in_reply_to.as_deref() == Some(name)
+                            ),
+                            "{context}: {effects:?}"
+                        );
+                        let after = serde_json::to_string(&table.state).unwrap();
+                        assert_eq!(before, after, "{context}");
+                        continue;
+                    }
+
+                    let mut expected_version = version;
+                    for effect in &effects {
+                        match effect {
+                            // the rest of the room only hears about it through broadcasts
+                            Effect::Reply(id, _) => assert_eq!(*id, session_id, "{context}"),
+                            // clients rely on patches arriving in order, with no gaps
+                            Effect::Broadcast(ServerMessage::Patch { version, .. }) => {
+                                expected_version += 1;
+                                assert_eq!(*version, expected_version, "{context}");
+                            }
+                            Effect::Broadcast(_) | Effect::Schedule(..) | Effect::Save => {}
+                        }
+                    }
+                }
+            }
+        }
+
+        // the judge is a player too, but sits out submitting
+        let table = at_step(&GameStep::Submission);
+        let judge = table.state.judge_id().unwrap();
+        assert!(senders.contains(&judge.as_str()));
+        let submission = ClientMessage::SubmitAcronym(Vec::new());
+        assert_eq!(
+            authorize(&submission, Some(&judge), &table.state),
+            Err(Denied::Judge)
+        );
+        assert_eq!(Denied::Judge.code(), ErrorCode::IsJudge);
+    }
+
+    #[test]
+    fn stale_timeouts_are_ignored() {
+        let mut table = Table::new(&["a", "b", "c"], Config::default());
+        let effects = table.send("a", ClientMessage::StartGame);
+        let Some(Effect::Schedule(_, submission_timeout)) = effects
+            .into_iter()
+            .find(|e| matches!(e, Effect::Schedule(_, Event::Timeout(_))))
+        else {
+            panic!("the submission timer should be scheduled");
+        };
+
+        // everyone submits early, which replaces the submission timer with the judging timer
+        table.submit_all();
+        assert_eq!(table.state.step, GameStep::Judging);
+        let effects = table.apply(submission_timeout);
+        assert!(effects.is_empty());
+        assert_eq!(table.state.step, GameStep::Judging);
+
+        // the judging timer still works
+        table.apply(Event::Timeout(table.state.timer_generation));
+        assert_eq!(table.state.step, GameStep::Submission);
+        assert_eq!(table.state.rounds.len(), 2);
+
+        // stopped timers don't fire either
+        table.send("a", ClientMessage::StopTimer);
+        table.apply(Event::Timeout(table.state.timer_generation - 1));
+        assert_eq!(table.state.step, GameStep::Submission);
+        assert_eq!(table.state.rounds.len(), 2);
+    }
+
+    #[test]
+    fn the_same_seed_plays_the_same_game() {
+        let play = |seed| {
+            let mut table = Table::new(&["a", "b", "c"], Config::default());
+            table.state.reseed(seed);
+            table.send("a", ClientMessage::StartGame);
+            table.submit_all();
+            // tokens are the one thing the seed doesn't decide
+            let submissions = table
+                .state
+                .anonymous_submissions()
+                .into_iter()
+                .map(|(_, submission)| submission)
+                .collect::<Vec<_>>();
+            let state = table.state;
+            (state.prompts, state.rounds[0].prompt.clone(), submissions)
+        };
+
+        assert_eq!(play(1), play(1));
+        assert_ne!(play(1), play(2));
+    }
+
+    #[test]
+    fn games_need_enough_players() {
+        let mut table = Table::new(&["a", "b"], Config::default());
+        let effects = table.send("a", ClientMessage::StartGame);
+        assert!(matches!(
+            effects.as_slice(),
+            [Effect::Reply(
+                _,
+                ServerMessage::Error {
+                    code: ErrorCode::NotAllowed,
+                    ..
+                }
+            )]
+        ));
+        assert_eq!(table.state.step, GameStep::Setup);
+
+        table.join("c");
+        table.send("a", ClientMessage::StartGame);
+        assert_eq!(table.state.step, GameStep::Submission);
+    }
+
+    #[test]
+    fn unplayable_configs_are_rejected() {
+        let mut table = Table::new(&["a", "b", "c"], Config::default());
+        let invalid = [
+            Config {
+                game_length: GameLength::RoundsPerJudge(usize::MAX),
+                ..Config::default()
+            },
+            Config {
+                target_score: Some(i64::MAX),
+                ..Config::default()
+            },
+            Config {
+                scoring: ScoringRules {
+                    audience_bonus: i64::MAX,
+                    ..ScoringRules::default()
+                },
+                ..Config::default()
+            },
+            Config {
+                letter_distribution: LetterDistribution::Custom(Vec::new()),
+                ..Config::default()
+            },
+            Config {
+                letter_distribution: LetterDistribution::Custom(vec![('a', 1), ('?', 1)]),
+                ..Config::default()
+            },
+            Config {
+                letter_distribution: LetterDistribution::Custom(vec![('A', usize::MAX)]),
+                ..Config::default()
+            },
+            Config {
+                prompt_packs: Vec::new(),
+                ..Config::default()
+            },
+            Config {
+                prompt_packs: vec!["no such pack".to_owned()],
+                ..Config::default()
+            },
+        ];
+        for config in invalid {
+            let effects = table.send("a", ClientMessage::UpdateConfig(config.clone()));
+            let rejected = effects.iter().any(|e| {
+                matches!(
+                    e,
+                    Effect::Reply(
+                        _,
+                        ServerMessage::Error {
+                            code: ErrorCode::InvalidConfig,
+                            ..
+                        }
+                    )
+                )
+            });
+            assert!(rejected, "{config:?}");
+            assert_eq!(table.state.config, Config::default());
+        }
+
+        let custom = Config {
+            letter_distribution: LetterDistribution::Custom(vec![('Ñ', 1), ('A', 0)]),
+            ..Config::default()
+        };
+        table.send("a", ClientMessage::UpdateConfig(custom.clone()));
+        assert_eq!(table.state.config, custom);
+    }
+
+    #[test]
+    fn player_prompts_are_capped() {
+        let mut table = Table::new(&["a", "b", "c"], Config::default());
+        let rejection = |effects: &[Effect]| match effects {
+            [Effect::Reply(_, ServerMessage::Error { code, .. })] => Some(*code),
+            _ => None,
+        };
+
+        let long = format!("___{}", "!".repeat(MAX_PROMPT_LENGTH));
+        let effects = table.send("b", ClientMessage::SubmitPrompt(long));
+        assert_eq!(rejection(&effects), Some(ErrorCode::InvalidPrompt));
+
+        for _ in 0..MAX_PROMPTS_PER_PLAYER {
+            let effects = table.send("b", ClientMessage::SubmitPrompt("Why ___?".to_owned()));
+            assert_eq!(rejection(&effects), None);
+        }
+        let effects = table.send("b", ClientMessage::SubmitPrompt("Why ___?".to_owned()));
+        assert_eq!(rejection(&effects), Some(ErrorCode::NotAllowed));
+
+        // removing a prompt that's already gone changes nothing
+        let id = table.state.player_prompts[0].id.clone();
+        table.send("b", ClientMessage::RemovePrompt(id.clone()));
+        let version = table.state.version;
+        let effects = table.send("b", ClientMessage::RemovePrompt(id));
+        assert_eq!(rejection(&effects), Some(ErrorCode::InvalidPrompt));
+        assert_eq!(table.state.version, version);
+    }
+
+    #[test]
+    fn tokens_dont_follow_the_seed() {
+        let tokens = || {
+            let mut table = Table::new(&["a", "b", "c"], Config::default());
+            table.state.reseed(1);
+            table.send("a", ClientMessage::StartGame);
+            table.submit_all();
+            table.state.rounds[0].tokens.clone()
+        };
+        assert_ne!(tokens(), tokens());
+    }
+
+    #[test]
+    fn reconnecting_sends_the_game_state_before_patches() {
+        let mut table = Table::new(&["a", "b", "c"], Config::default());
+        table.send("b", ClientMessage::Disconnect);
+        table.send("c", ClientMessage::Disconnect);
+        // nobody is around to take over as host
+        table.send("a", ClientMessage::KickPlayer("a".to_owned()));
+        assert_eq!(table.state.host, Some("a".to_owned()));
+
+        let effects = table.send("b", ClientMessage::Connect("b".to_owned()));
+        let Some((Effect::Reply(_, ServerMessage::GameState(state)), patches)) =
+            effects.split_first()
+        else {
+            panic!("the game state should go out first: {effects:?}");
+        };
+        // coming back online, and taking over as host
+        assert!(matches!(patches.last(), Some(Effect::Save)));
+        let patches = &patches[..patches.len() - 1];
+        assert_eq!(patches.len(), 2);
+        assert!(patches.iter().all(|p| matches!(
+            p,
+            Effect::Broadcast(ServerMessage::Patch { version, .. }) if *version <= state.version
+        )));
+    }
+
+    #[test]
+    fn only_changes_are_saved() {
+        let saved = |effects: Vec<Effect>| effects.iter().any(|e| matches!(e, Effect::Save));
+        let mut table = Table::new(&["a", "b", "c"], Config::default());
+        assert!(saved(table.send("a", ClientMessage::StartGame)));
+
+        let effects = table.send("b", ClientMessage::GetRemainingTime);
+        assert!(matches!(
+            effects.as_slice(),
+            [Effect::Reply(
+                _,
+                ServerMessage::UpdateRemainingTime(Some(_))
+            )]
+        ));
+        assert!(!saved(table.send("b", ClientMessage::Resync)));
+        assert!(!saved(table.send("b", ClientMessage::StartGame)));
+
+        // changing your submission doesn't send a patch, but it still needs saving
+        let judge = table.state.judge_id().unwrap();
+        let author = ["a", "b", "c"].into_iter().find(|id| *id != judge).unwrap();
+        let acronym = table.state.rounds[0].prompt.acronym.clone();
+        for _ in 0..2 {
+            let submission = ClientMessage::SubmitAcronym(letters(&acronym));
+            assert!(saved(table.send(author, submission)));
+        }
+    }
+
+    #[test]
+    fn players_who_reconnect_in_time_stay() {
+        let mut table = Table::new(&["a", "b", "c"], Config::default());
+        let grace_expired = |effects: Vec<Effect>| {
+            effects
+                .into_iter()
+                .find_map(|e| match e {
+                    Effect::Schedule(_, event @ Event::GraceExpired(..)) => Some(event),
+                    _ => None,
+                })
+                .unwrap()
+        };
+
+        let first = grace_expired(table.send("b", ClientMessage::Disconnect));
+        table.now += Duration::from_secs(1);
+        table.send("b", ClientMessage::Connect("b".to_owned()));
+        let second = grace_expired(table.send("b", ClientMessage::Disconnect));
+
+        // the first grace period was interrupted by reconnecting
+        table.apply(first);
+        assert!(table.state.is_player(&"b".to_owned()));
+        table.apply(second);
+        assert!(!table.state.is_player(&"b".to_owned()));
+    }
+
+    #[test]
+    fn players_who_dont_return_after_a_restart_quit() {
+        let mut table = Table::new(&["a", "b", "c"], Config::default());
+        let json = serde_json::to_string(&table.state).unwrap();
+        table.state = serde_json::from_str(&json).unwrap();
+        table.sessions = Sessions::new();
+        let effects = restore(&mut table.state, None, table.now);
+        assert!(table.state.players.values().all(|p| !p.connected));
+
+        table.send("b", ClientMessage::Connect("b".to_owned()));
+        for effect in effects {
+            if let Effect::Schedule(_, event) = effect {
+                table.apply(event);
+            }
+        }
+        assert!(table.state.is_player(&"b".to_owned()));
+        assert!(!table.state.is_player(&"a".to_owned()));
+        assert!(!table.state.is_player(&"c".to_owned()));
+        // the host never came back
+        assert_eq!(table.state.host, Some("b".to_owned()));
+    }
+
+    #[test]
+    fn bots_fill_out_a_game() {
+        let config = Config {
+            game_length: GameLength::RoundsPerJudge(1),
+            ..Config::default()
+        };
+        let mut table = Table::new(&["a"], config);
+        table.send("a", ClientMessage::AddBot);
+        table.send("a", ClientMessage::AddBot);
+        assert_eq!(table.state.active_bots().count(), 2);
+        assert!(table.state.is_name_taken(&"a".to_owned(), "Bot 2"));
+
+        // the bots take their turns, and "a" takes theirs once the bots are done
+        let a = "a".to_owned();
+        let mut effects = table.send("a", ClientMessage::StartGame);
+        for _ in 0..100 {
+            if table.state.step == GameStep::Results {
+                break;
+            }
+            let bots_play = effects.into_iter().find_map(|e| match e {
+                Effect::Schedule(delay, event @ Event::BotsPlay(_)) => {
+                    assert_eq!(delay, bots::THINKING_TIME);
+                    Some(event)
+                }
+                _ => None,
+            });
+            let round = table.state.rounds.last().unwrap();
+            effects = if let Some(event) = bots_play {
+                table.apply(event)
+            } else if round.winner.is_some() {
+                table.apply(Event::Timeout(table.state.timer_generation))
+            } else if table.state.step == GameStep::Submission {
+                let submission = letters(&round.prompt.acronym);
+                table.send("a", ClientMessage::SubmitAcronym(submission))
+            } else {
+                assert_eq!(table.state.judge_id(), Some(a.clone()));
+                let token = table.state.anonymous_submissions()[0].0.clone();
+                table.send("a", ClientMessage::JudgeRound(token))
+            };
+        }
+
+        assert_eq!(table.state.step, GameStep::Results);
+        assert_eq!(table.state.rounds.len(), 3);
+        for round in &table.state.rounds {
+            assert_eq!(round.submissions.len(), 2);
+            assert!(round.winner.is_some());
+        }
+    }
+
+    #[test]
+    fn whoever_takes_over_judging_loses_their_submission() {
+        let mut table = Table::new(&["a", "b", "c", "d"], Config::default());
+        table.send("a", ClientMessage::StartGame);
+        let judge = table.state.judge_id().unwrap();
+        if judge == "a" {
+            table.send("a", ClientMessage::TransferHost("b".to_owned()));
+        }
+        let host = table.state.host.clone().unwrap();
+        let acronym = table.state.rounds[0].prompt.acronym.clone();
+        table.send(&host, ClientMessage::SubmitAcronym(letters(&acronym)));
+
+        let effects = table.send(&host, ClientMessage::KickPlayer(judge));
+        assert!(effects.iter().any(|e| matches!(
+            e,
+            Effect::Broadcast(ServerMessage::Patch {
+                patch: StatePatch::SubmissionWithdrawn,
+                ..
+            })
+        )));
+        assert_eq!(table.state.acting_judge(), Some(host.clone()));
+        assert!(!table.state.rounds[0].submissions.contains_key(&host));
+
+        table.submit_all();
+        assert_eq!(table.state.step, GameStep::Judging);
+        assert_eq!(table.state.anonymous_submissions().len(), 2);
+    }
+
+    #[test]
+    fn bots_vote_for_someone_else() {
+        let config = Config {
+            mode: GameMode::HeadToHead,
+            ..Config::default()
+        };
+        let mut table = Table::new(&["a"], config);
+        table.send("a", ClientMessage::AddBot);
+        table.send("a", ClientMessage::AddBot);
+        table.send("a", ClientMessage::StartGame);
+
+        table.apply(Event::BotsPlay(table.state.timer_generation));
+        table.submit_all();
+        assert_eq!(table.state.step, GameStep::Voting);
+        table.apply(Event::BotsPlay(table.state.timer_generation));
+
+        let round = table.state.rounds.last().unwrap();
+        assert_eq!(round.votes.len(), 2);
+        assert!(round
+            .votes
+            .iter()
+            .all(|(voter, candidate)| voter != candidate));
+    }
+}
@@ -0,0 +1,474 @@
+use super::engine::{self, Effect, Event};
+use super::prompt_packs;
+use super::types::*;
+use crate::extensions::ResultExt;
+use ::leptos::logging::log;
+use ::serde::{Deserialize, Serialize};
+use ::std::fs::OpenOptions;
+use ::std::io::{self, Write};
+use ::std::path::{Path, PathBuf};
+use ::std::time::{Duration, Instant};
+use ::tokio::sync::mpsc;
+use ::tokio::task::spawn_blocking;
+
+/// Directory where event logs get written, one json lines file per room.
+/// Logging is disabled unless this environment variable is set.
+const LOG_DIR_VAR: &str = "ACRONYMIA_LOG_DIR";
+
+/// One line of a game's event log.
+/// Replaying the lines through the engine rebuilds the game, see `Replay`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Entry {
+    /// The room was created. Always the first line.
+    Created { seed: u64 },
+    /// The server restarted, and the room was restored from its snapshot
+    Restored { timer: Option<(TimerTag, Duration)> },
+    /// An event was applied to the game, this long after the room was created or restored
+    Event { at: Duration, event: LoggedEvent },
+}
+
+/// `engine::Event`, with times relative to the start of the log
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum LoggedEvent {
+    Message(SessionId, ClientMessage),
+    Timeout(u64),
+    GraceExpired(PlayerId, Duration),
+    BotsPlay(u64),
+}
+
+/// Appends a room's events to its log file.
+/// The file is written in the background, so the state thread never waits on the disk.
+/// No-op if logging is disabled.
+pub struct GameLog {
+    /// Lines for the writer, see `spawn_writer`
+    writer: Option<mpsc::UnboundedSender<String>>,
+    /// Times in the log are relative to this
+    epoch: Instant,
+}
+
+fn log_dir() -> Option<PathBuf> {
+    std::env::var_os(LOG_DIR_VAR).map(PathBuf::from)
+}
+
+/// Writes lines to the file in the background, see `write_lines`
+fn spawn_writer(dir: PathBuf, code: &str, replace: bool) -> mpsc::UnboundedSender<String> {
+    let path = dir.join(format!("{code}.jsonl"));
+    let (sender, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(write_lines(dir, path, replace, receiver));
+    sender
+}
+
+/// Writes lines to the file in the order they're sent, until the sender is dropped.
+/// Lines that pile up during a write go out together.
+async fn write_lines(
+    dir: PathBuf,
+    path: PathBuf,
+    replace: bool,
+    mut receiver: mpsc::UnboundedReceiver<String>,
+) {
+    let open = move || {
+        std::fs::create_dir_all(&dir)?;
+        OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(replace)
+            .append(!replace)
+            .open(path)
+    };
+    let Some(mut file) = spawn_blocking(open)
+        .await
+        .ok_or_log()
+        .and_then(|f| f.ok_or_log())
+    else {
+        return;
+    };
+
+    while let Some(mut lines) = receiver.recv().await {
+        while let Ok(line) = receiver.try_recv() {
+            lines.push_str(&line);
+        }
+        let write = move || {
+            file.write_all(lines.as_bytes()).ok_or_log();
+            file
+        };
+        let Some(f) = spawn_blocking(write).await.ok_or_log() else {
+            return;
+        };
+        file = f;
+    }
+}
+
+impl GameLog {
+    /// Starts a new log, replacing any older game's log with the same room code
+    pub fn create(code: &str, seed: u64, now: Instant) -> Self {
+        let log = GameLog {
+            writer: log_dir().map(|dir| spawn_writer(dir, code, true)),
+            epoch: now,
+        };
+        log.append(&Entry::Created { seed });
+        log
+    }
+
+    /// Picks the log back up after a server restart
+    pub fn restore(code: &str, timer: Option<(TimerTag, Duration)>, now: Instant) -> Self {
+        let log = GameLog {
+            writer: log_dir().map(|dir| spawn_writer(dir, code, false)),
+            epoch: now,
+        };
+        log.append(&Entry::Restored { timer });
+        log
+    }
+
+    /// Call before applying the event
+    pub fn record(&self, event: &Event, state: &GameState, now: Instant) {
+        if self.writer.is_some() {
+            self.append(&self.entry(event, state, now));
+        }
+    }
+
+    fn entry(&self, event: &Event, state: &GameState, now: Instant) -> Entry {
+        let event = match event.clone() {
+            Event::Message(session_id, message) => {
+                let round = state.rounds.last();
+                let message = map_token(message, |token| {
+                    round
+                        .and_then(|r| r.author_of(&token))
+                        .cloned()
+                        .unwrap_or(token)
+                });
+                LoggedEvent::Message(session_id, message)
+            }
+            Event::Timeout(generation) => LoggedEvent::Timeout(generation),
+            Event::GraceExpired(id, offline_since) => {
+                LoggedEvent::GraceExpired(id, offline_since.saturating_duration_since(self.epoch))
+            }
+            Event::BotsPlay(generation) => LoggedEvent::BotsPlay(generation),
+        };
+        Entry::Event {
+            at: now.saturating_duration_since(self.epoch),
+            event,
+        }
+    }
+
+    fn append(&self, entry: &Entry) {
+        if let Some(writer) = &self.writer {
+            _ = writer.send(line(entry));
+        }
+    }
+}
+
+/// Submission tokens are random every time, so a replay can't count on getting the same ones.
+/// Messages that pick a submission are logged with its author in place of the token.
+fn map_token(message: ClientMessage, f: impl FnOnce(String) -> String) -> ClientMessage {
+    match message {
+        ClientMessage::JudgeRound(token) => ClientMessage::JudgeRound(f(token)),
+        ClientMessage::CastVote(token) => ClientMessage::CastVote(f(token)),
+        ClientMessage::AudienceVote(token) => ClientMessage::AudienceVote(f(token)),
+        message => message,
+    }
+}
+
+fn line(entry: &Entry) -> String {
+    let mut line = serde_json::to_string(entry).unwrap_or_default();
+    line.push('\n');
+    line
+}
+
+/// Reads a log file written by `GameLog`
+pub fn read(path: &Path) -> io::Result<Vec<Entry>> {
+    std::fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(io::Error::other))
+        .collect()
+}
+
+/// Rebuilds a game by running its log back through the engine.
+/// The clock starts whenever the replay does, and follows the times in the log.
+pub struct Replay {
+    pub state: GameState,
+    sessions: Sessions,
+    epoch: Instant,
+    now: Instant,
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        let now = Instant::now();
+        Replay {
+            state: GameState::default(),
+            sessions: Sessions::new(),
+            epoch: now,
+            now,
+        }
+    }
+}
+
+impl Replay {
+    /// Applies the next line of the log, the same way the room's driver did
+    pub fn apply(&mut self, entry: Entry) -> Vec<Effect> {
+        match entry {
+            Entry::Created { seed } => {
+                self.state = game_state_init();
+                self.state.reseed(seed);
+                self.sessions = Sessions::new();
+                self.epoch = self.now;
+                Vec::new()
+            }
+            Entry::Restored { timer } => {
+                // anything that isn't saved in snapshots was lost in the restart
+                let json = serde_json::to_string(&self.state).ok_or_log();
+                if let Some(state) = json.and_then(|json| serde_json::from_str(&json).ok_or_log()) {
+                    self.state = state;
+                }
+                self.sessions = Sessions::new();
+                self.epoch = self.now;
+                engine::restore(&mut self.state, timer, self.now)
+            }
+            Entry::Event { at, event } => {
+                self.now = self.epoch + at;
+                let event = match event {
+                    LoggedEvent::Message(session_id, message) => {
+                        let round = self.state.rounds.last();
+                        let message = map_token(message, |author| {
+                            round
+                                .and_then(|r| r.tokens.get(&author))
+                                .cloned()
+                                .unwrap_or(author)
+                        });
+                        Event::Message(session_id, message)
+                    }
+                    LoggedEvent::Timeout(generation) => Event::Timeout(generation),
+                    LoggedEvent::GraceExpired(id, since) => {
+                        Event::GraceExpired(id, self.epoch + since)
+                    }
+                    LoggedEvent::BotsPlay(generation) => Event::BotsPlay(generation),
+                };
+                engine::apply(&mut self.state, &mut self.sessions, event, self.now)
+            }
+        }
+    }
+}
+
+/// The game state after the first `events` events in the log
+pub fn replay(entries: impl IntoIterator<Item = Entry>, events: usize) -> GameState {
+    let mut replay = Replay::default();
+    let mut applied = 0;
+    for entry in entries {
+        match entry {
+            // a restart after the last event belongs to what comes next
+            Entry::Event { .. } | Entry::Restored { .. } if applied == events => break,
+            Entry::Event { .. } => applied += 1,
+            _ => {}
+        }
+        replay.apply(entry);
+    }
+    replay.state
+}
+
+/// `acronymia replay <log file> [events]`
+/// Prints the game state after that many events, or after the whole log.
+pub fn replay_command(args: &[String]) -> io::Result<()> {
+    let Some(path) = args.first() else {
+        log!("usage: acronymia replay <log file> [events]");
+        return Ok(());
+    };
+    let events = args
+        .get(1)
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(usize::MAX);
+
+    prompt_packs::init();
+    let state = replay(read(Path::new(path))?, events);
+    println!("{}", serde_json::to_string_pretty(&state)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::testing::*;
+
+    /// Stands in for the room's driver: applies events, fires scheduled ones in order,
+    /// and keeps the log along with the state after every event.
+    struct Recorder {
+        table: Table,
+        game_log: GameLog,
+        entries: Vec<Entry>,
+        states: Vec<serde_json::Value>,
+        scheduled: Vec<(Instant, Event)>,
+    }
+
+    impl Recorder {
+        fn new(seed: u64) -> Self {
+            let now = Instant::now();
+            let mut state = game_state_init();
+            state.reseed(seed);
+            Recorder {
+                table: Table {
+                    state,
+                    sessions: Sessions::new(),
+                    now,
+                },
+                game_log: GameLog {
+                    writer: None,
+                    epoch: now,
+                },
+                entries: vec![Entry::Created { seed }],
+                states: Vec::new(),
+                scheduled: Vec::new(),
+            }
+        }
+
+        fn schedule(&mut self, effects: &[Effect]) {
+            for effect in effects {
+                if let Effect::Schedule(delay, event) = effect {
+                    self.scheduled
+                        .push((self.table.now + *delay, event.clone()));
+                }
+            }
+        }
+
+        /// Moves the clock forward, firing anything that was scheduled in the meantime
+        fn wait(&mut self, secs: u64) {
+            let until = self.table.now + Duration::from_secs(secs);
+            while let Some(next) = (0..self.scheduled.len())
+                .filter(|i| self.scheduled[*i].0 <= until)
+                .min_by_key(|i| self.scheduled[*i].0)
+            {
+                let (at, event) = self.scheduled.remove(next);
+                self.table.now = at;
+                self.apply(event);
+            }
+            self.table.now = until;
+        }
+
+        /// Same as the server restarting, and restoring the room from its snapshot
+        fn restart(&mut self) {
+            let Table {
+                state,
+                sessions,
+                now,
+            } = &mut self.table;
+            let json = serde_json::to_string(state).unwrap();
+            let timer = state.timer.tag().cloned().zip(state.timer.remaining(*now));
+            *state = serde_json::from_str(&json).unwrap();
+            *sessions = Sessions::new();
+            self.scheduled.clear();
+            self.game_log.epoch = *now;
+            self.entries.push(Entry::Restored {
+                timer: timer.clone(),
+            });
+            let effects = engine::restore(state, timer, *now);
+            self.schedule(&effects);
+        }
+    }
+
+    impl Play for Recorder {
+        fn state(&self) -> &GameState {
+            &self.table.state
+        }
+
+        fn apply(&mut self, event: Event) -> Vec<Effect> {
+            let entry = self
+                .game_log
+                .entry(&event, &self.table.state, self.table.now);
+            self.entries.push(entry);
+            let effects = self.table.apply(event);
+            self.schedule(&effects);
+            self.states
+                .push(serde_json::to_value(&self.table.state).unwrap());
+            effects
+        }
+    }
+
+    /// Submission tokens are new every time, see `map_token`
+    fn without_tokens(mut state: serde_json::Value) -> serde_json::Value {
+        for round in state["rounds"].as_array_mut().into_iter().flatten() {
+            round["tokens"] = serde_json::Value::Null;
+        }
+        state
+    }
+
+    /// Feeds the log back through the engine, and checks every step matches the recording
+    fn check_replay(recorder: &Recorder) {
+        let events = recorder.states.len();
+        assert!(events > 0);
+        for (i, recorded) in recorder.states.iter().enumerate() {
+            let replayed = replay(recorder.entries.clone(), i + 1);
+            let replayed = serde_json::to_value(&replayed).unwrap();
+            assert_eq!(
+                without_tokens(replayed),
+                without_tokens(recorded.clone()),
+                "event {i}: {:?}",
+                recorder.entries
+            );
+        }
+    }
+
+    #[test]
+    fn replays_rebuild_the_game() {
+        let mut game = Recorder::new(7);
+        for id in ["a", "b", "c", "d"] {
+            game.join(id);
+        }
+        game.send("b", ClientMessage::SubmitPrompt("Why ___?".to_owned()));
+        game.send("a", ClientMessage::StartGame);
+
+        // a round where the judge picks a winner
+        game.wait(5);
+        game.submit_all();
+        let judge = game.state().judge_id().unwrap();
+        let token = game.state().anonymous_submissions()[0].0.clone();
+        game.send(&judge, ClientMessage::JudgeRound(token));
+
+        // a round where the timers run out, and someone leaves for good
+        game.wait(15);
+        game.send("d", ClientMessage::Disconnect);
+        game.wait(300);
+
+        game.restart();
+        game.join("a");
+        game.wait(60);
+        game.send("a", ClientMessage::ResetState);
+        game.join("a");
+
+        check_replay(&game);
+    }
+
+    #[tokio::test]
+    async fn lines_are_written_in_order() {
+        let dir = TempDir::new();
+        let lines = (0..100).map(|i| format!("{i}\n")).collect::<Vec<_>>();
+        // the second writer appends to what the first one wrote
+        for replace in [true, false] {
+            let (sender, receiver) = mpsc::unbounded_channel();
+            for line in &lines {
+                sender.send(line.clone()).unwrap();
+            }
+            drop(sender);
+            let dir = dir.path().to_owned();
+            write_lines(dir.clone(), dir.join("WXYZ.jsonl"), replace, receiver).await;
+        }
+
+        let text = std::fs::read_to_string(dir.path().join("WXYZ.jsonl")).unwrap();
+        assert_eq!(text, lines.concat().repeat(2));
+    }
+
+    #[test]
+    fn logs_are_json_lines() {
+        let mut game = Recorder::new(1);
+        game.join("a");
+        game.send("a", ClientMessage::GetRemainingTime);
+
+        let text = game.entries.iter().map(line).collect::<String>();
+        let dir = TempDir::new();
+        let path = dir.path().join("ABCD.jsonl");
+        std::fs::write(&path, text).unwrap();
+
+        let entries = read(&path).unwrap();
+        assert_eq!(entries.len(), game.entries.len());
+        let replayed = serde_json::to_value(replay(entries, usize::MAX)).unwrap();
+        assert_eq!(Some(&replayed), game.states.last());
+    }
+}
@@ -1,61 +1,116 @@
-use crate::types::Range;
+use crate::types::{LetterDistribution, Range};
 use rand::prelude::*;
 
-/// Based on a
-/// [Wikipedia](https://en.wikipedia.org/wiki/Letter_frequency#Relative_frequencies_of_the_first_letters_of_a_word_in_English_language).
-/// table of the relative frequency of each letter as the first letter in a word.
-/// I'v copied those values, multiplying every percentage by 100 to get whole integers.
-const DISTRIBUTION: [(char, usize); 26] = [
-    ('S', 1100),
-    ('C', 940),
-    ('P', 770),
-    ('D', 610),
-    ('B', 600),
-    ('R', 600),
-    ('A', 570),
-    ('M', 560),
-    ('T', 500),
-    ('F', 410),
-    ('E', 390),
-    ('I', 390),
-    ('H', 370),
-    ('G', 330),
-    ('L', 310),
-    ('U', 290),
-    ('W', 270),
-    ('O', 250),
-    ('N', 220),
-    ('V', 150),
-    ('J', 110),
-    ('K', 100),
-    ('Q', 49),
-    ('Y', 36),
-    ('Z', 24),
-    ('X', 5),
+/// Chance a pronounceable acronym starts with a vowel, rather than a consonant
+const VOWEL_START_PROBABILITY: f64 = 0.3;
+
+/// Chance a consonant is followed by a second one, when they make a common cluster like "ST"
+const CLUSTER_PROBABILITY: f64 = 0.25;
+
+/// Consonant pairs that are easy to pronounce, at the start or end of a syllable
+const CLUSTERS: [[char; 2]; 24] = [
+    ['B', 'L'],
+    ['B', 'R'],
+    ['C', 'H'],
+    ['C', 'L'],
+    ['C', 'R'],
+    ['D', 'R'],
+    ['F', 'L'],
+    ['F', 'R'],
+    ['G', 'L'],
+    ['G', 'R'],
+    ['N', 'D'],
+    ['N', 'T'],
+    ['P', 'L'],
+    ['P', 'R'],
+    ['S', 'H'],
+    ['S', 'K'],
+    ['S', 'L'],
+    ['S', 'M'],
+    ['S', 'N'],
+    ['S', 'P'],
+    ['S', 'T'],
+    ['T', 'H'],
+    ['T', 'R'],
+    ['W', 'H'],
 ];
 
-const SUM_OF_WEIGHTS: usize = {
-    let mut total: usize = 0;
-    let mut i = 0;
-    // iter() is not allowed at compile time, but basic loops are!
-    while i < DISTRIBUTION.len() {
-        total += DISTRIBUTION[i].1;
-        i += 1;
+/// Makes a random selection of n letters, using the relative weights of the letter distribution.
+/// Not necessarily pronounceable, so it's an initialism, not an acronym.
+/// Falls back to the English distribution if the custom one has no letters.
+pub fn random_initialism(
+    range: &Range<usize>,
+    distribution: &LetterDistribution,
+    rng: &mut impl Rng,
+) -> String {
+    let mut weights = distribution.weights();
+    let mut sum_of_weights = sum(&weights);
+    if sum_of_weights == 0 {
+        weights = LetterDistribution::English.weights();
+        sum_of_weights = sum(&weights);
     }
-    total
-};
 
-/// Makes a random selection of n letters, using relative dictionary frequencies.
-/// Not necessarily pronounceable, so it's an initialism, not an acronym.
-pub fn random_initialism(range: &Range<usize>) -> String {
-    let mut rng = rand::thread_rng();
     let length = rng.gen_range(range.min..=range.max);
-    (0..length).map(|_| random_letter()).collect()
+    (0..length)
+        .map(|_| random_letter(&weights, sum_of_weights, rng))
+        .collect()
+}
+
+/// Makes a random selection of n letters, that can be read out loud as a word.
+/// Vowels & consonants alternate, except for the occasional consonant cluster.
+/// Letters are still weighted by the letter distribution.
+/// Falls back to an initialism if the distribution is missing either vowels or consonants.
+pub fn random_acronym(
+    range: &Range<usize>,
+    distribution: &LetterDistribution,
+    rng: &mut impl Rng,
+) -> String {
+    let weights = distribution.weights();
+    let (vowels, consonants): (Vec<_>, Vec<_>) =
+        weights.iter().copied().partition(|(c, _)| is_vowel(*c));
+    let (vowel_sum, consonant_sum) = (sum(&vowels), sum(&consonants));
+    if vowel_sum == 0 || consonant_sum == 0 {
+        return random_initialism(range, distribution, rng);
+    }
+
+    let length = rng.gen_range(range.min..=range.max);
+    let mut acronym = Vec::with_capacity(length);
+    let mut vowel_next = rng.gen_bool(VOWEL_START_PROBABILITY);
+    while acronym.len() < length {
+        if vowel_next {
+            acronym.push(random_letter(&vowels, vowel_sum, rng));
+        } else {
+            let first = random_letter(&consonants, consonant_sum, rng);
+            acronym.push(first);
+
+            let cluster = consonants
+                .iter()
+                .filter(|(c, _)| CLUSTERS.contains(&[first, *c]))
+                .copied()
+                .collect::<Vec<_>>();
+            let cluster_sum = sum(&cluster);
+            if acronym.len() < length && cluster_sum > 0 && rng.gen_bool(CLUSTER_PROBABILITY) {
+                acronym.push(random_letter(&cluster, cluster_sum, rng));
+            }
+        }
+        vowel_next = !vowel_next;
+    }
+    acronym.into_iter().collect()
+}
+
+fn is_vowel(letter: char) -> bool {
+    matches!(
+        letter.to_uppercase().next().unwrap_or(letter),
+        'A' | 'E' | 'I' | 'O' | 'U' | 'Ä' | 'Ö' | 'Ü' | 'Á' | 'É' | 'Í' | 'Ó' | 'Ú'
+    )
+}
+
+fn sum(weights: &[(char, usize)]) -> usize {
+    weights.iter().map(|(_, weight)| weight).sum()
 }
 
-fn random_letter() -> char {
-    let mut rng = rand::thread_rng();
-    let mut value = rng.gen_range(0..SUM_OF_WEIGHTS);
+fn random_letter(weights: &[(char, usize)], sum_of_weights: usize, rng: &mut impl Rng) -> char {
+    let mut value = rng.gen_range(0..sum_of_weights);
 
     // The basic idea is:
     // If 0 <= value < weight_0, then return the first letter in the array.
@@ -63,12 +118,39 @@ fn random_letter() -> char {
     //
     // But we can optimize by subtracting each weight if there's no match yet.
     // This works out to the same without keeping a running subtally of weights.
-    for (letter, weight) in DISTRIBUTION {
-        if value < weight {
-            return letter;
+    for (letter, weight) in weights {
+        if value < *weight {
+            return *letter;
         } else {
             value -= weight;
         }
     }
     panic!("impossible")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::random::GameRng;
+
+    #[test]
+    fn acronyms_are_pronounceable() {
+        let range = Range { min: 2, max: 8 };
+        let mut rng = GameRng::new(0);
+        for distribution in LetterDistribution::PRESETS {
+            for _ in 0..1000 {
+                let acronym = random_acronym(&range, &distribution, &mut rng)
+                    .chars()
+                    .collect::<Vec<_>>();
+                for pair in acronym.windows(2) {
+                    // vowels never double up
+                    assert!(!(is_vowel(pair[0]) && is_vowel(pair[1])), "{:?}", acronym);
+                    // consonants only double up as a cluster
+                    if !is_vowel(pair[0]) && !is_vowel(pair[1]) {
+                        assert!(CLUSTERS.contains(&[pair[0], pair[1]]), "{:?}", acronym);
+                    }
+                }
+            }
+        }
+    }
+}
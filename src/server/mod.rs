@@ -1,6 +1,13 @@
+pub mod bots;
+pub mod engine;
+pub mod game_log;
 pub mod letter_bag;
+pub mod permissions;
+pub mod prompt_packs;
 pub mod random;
-pub mod state;
+pub mod snapshot;
 pub mod sync;
+#[cfg(test)]
+mod testing;
 pub mod types;
 pub mod ws;
@@ -0,0 +1,200 @@
+use super::types::*;
+use crate::constants::DEV_MODE;
+use ::std::fmt;
+
+/// What a session is to the game, which decides the messages it's allowed to send.
+/// A session can have more than one role, e.g. the host is usually a player too.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// Runs the game
+    Host,
+    /// Picks this round's winner
+    Judge,
+    /// Joined the game during setup
+    Player,
+    /// Watching the game, instead of playing
+    Audience,
+    /// Any connected session, whether or not they have joined
+    Anyone,
+}
+
+impl Role {
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Host => "the host",
+            Role::Judge => "the judge",
+            Role::Player => "players in the game",
+            Role::Audience => "the audience",
+            Role::Anyone => "anyone",
+        }
+    }
+}
+
+/// Who can send a message, and when
+pub struct Permission {
+    /// Sending is allowed with any one of these roles
+    pub roles: &'static [Role],
+    /// Empty means every step
+    pub steps: &'static [GameStep],
+}
+
+/// Why a message was rejected
+#[derive(Clone, Debug, PartialEq)]
+pub enum Denied {
+    /// The session doesn't have any of the allowed roles
+    Role(&'static [Role]),
+    /// The message doesn't make sense at the current step
+    Step(GameStep),
+    /// The judge tried to submit, and could have picked their own submission
+    Judge,
+}
+
+impl Denied {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Denied::Step(_) => ErrorCode::WrongStep,
+            Denied::Judge => ErrorCode::IsJudge,
+            Denied::Role(roles) => match roles.first() {
+                Some(Role::Host) => ErrorCode::NotHost,
+                Some(Role::Judge) => ErrorCode::NotJudge,
+                Some(Role::Player) => ErrorCode::NotJoined,
+                Some(Role::Audience) => ErrorCode::NotAudience,
+                Some(Role::Anyone) | None => ErrorCode::NotAllowed,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Denied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Denied::Role(roles) => {
+                let names = roles.iter().map(|r| r.name()).collect::<Vec<_>>();
+                write!(f, "Only {} can do that", names.join(" or "))
+            }
+            Denied::Step(step) => write!(f, "You can't do that during {step:?}"),
+            Denied::Judge => write!(f, "The judge doesn't submit this round"),
+        }
+    }
+}
+
+const EVERY_STEP: &[GameStep] = &[];
+
+/// The permission table.
+/// Every message is listed, so new messages have to pick who can send them.
+pub fn permission(message: &ClientMessage) -> Permission {
+    use ClientMessage as M;
+    use GameStep as S;
+    use Role as R;
+
+    let (roles, steps): (&[Role], &[GameStep]) = match message {
+        M::Connect(_)
+        | M::Disconnect
+        | M::JoinGame { .. }
+        | M::JoinAudience { .. }
+        | M::GetRemainingTime
+        | M::Resync => (&[R::Anyone], EVERY_STEP),
+        // debug messages are open to everyone in dev mode
+        M::ResetState | M::StopTimer if DEV_MODE => (&[R::Anyone], EVERY_STEP),
+        M::KickPlayer(_) | M::TransferHost(_) | M::ResetState | M::StopTimer => {
+            (&[R::Host], EVERY_STEP)
+        }
+        M::UpdateConfig(_) | M::StartGame | M::AddBot => (&[R::Host], &[S::Setup]),
+        M::SubmitPrompt(_) | M::RemovePrompt(_) => (&[R::Player], &[S::Setup]),
+        M::SubmitAcronym(_) => (&[R::Player], &[S::Submission]),
+        M::JudgeRound(_) => (&[R::Judge], &[S::Judging]),
+        M::CastVote(_) => (&[R::Player], &[S::Voting]),
+        M::AudienceVote(_) => (&[R::Audience], &[S::Judging, S::Voting]),
+    };
+    Permission { roles, steps }
+}
+
+/// Every role the player has in the game right now
+pub fn roles(player_id: Option<&PlayerId>, state: &GameState) -> Vec<Role> {
+    let mut roles = vec![Role::Anyone];
+    let Some(id) = player_id else {
+        return roles;
+    };
+    if state.is_host(id) {
+        roles.push(Role::Host);
+    }
+    if state.acting_judge().as_ref() == Some(id) {
+        roles.push(Role::Judge);
+    }
+    if state.is_player(id) {
+        roles.push(Role::Player);
+    }
+    if state.is_audience(id) {
+        roles.push(Role::Audience);
+    }
+    roles
+}
+
+/// Checks the message against the permission table.
+/// Steps are checked first, since everyone gets the same answer for those.
+/// The judge is also a player, but can't submit outside head to head mode.
+pub fn authorize(
+    message: &ClientMessage,
+    player_id: Option<&PlayerId>,
+    state: &GameState,
+) -> Result<(), Denied> {
+    let permission = permission(message);
+    if !permission.steps.is_empty() && !permission.steps.contains(&state.step) {
+        return Err(Denied::Step(state.step.clone()));
+    }
+
+    let roles = roles(player_id, state);
+    if !permission.roles.iter().any(|r| roles.contains(r)) {
+        return Err(Denied::Role(permission.roles));
+    }
+
+    let is_judge = player_id.is_some_and(|id| state.acting_judge().as_ref() == Some(id));
+    if is_judge && matches!(message, ClientMessage::SubmitAcronym(_)) {
+        return Err(Denied::Judge);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_judge_can_judge() {
+        let mut state = demo_init(vec!["a", "b", "c"]);
+        state.step = GameStep::Judging;
+        state.rounds = vec![Round::default()];
+        let judge = state.judge_id().unwrap();
+        let message = ClientMessage::JudgeRound(String::new());
+
+        assert_eq!(authorize(&message, Some(&judge), &state), Ok(()));
+        assert_eq!(
+            authorize(&message, Some(&"b".to_owned()), &state),
+            Err(Denied::Role(&[Role::Judge]))
+        );
+
+        state.step = GameStep::Submission;
+        assert_eq!(
+            authorize(&message, Some(&judge), &state),
+            Err(Denied::Step(GameStep::Submission))
+        );
+    }
+
+    #[test]
+    fn only_the_host_can_start() {
+        let state = demo_init(vec!["a", "b", "c"]);
+        let message = ClientMessage::StartGame;
+        let host = state.host.clone().unwrap();
+        let guest = state.rotation.iter().find(|id| **id != host).unwrap();
+
+        assert_eq!(authorize(&message, Some(&host), &state), Ok(()));
+        assert_eq!(
+            authorize(&message, Some(guest), &state),
+            Err(Denied::Role(&[Role::Host]))
+        );
+        assert_eq!(
+            authorize(&message, None, &state),
+            Err(Denied::Role(&[Role::Host]))
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use super::random::shuffle;
+use super::types::*;
+use ::leptos::logging::log;
+use ::rand::Rng;
+use ::serde::Deserialize;
+use ::std::collections::HashSet;
+use ::std::path::Path;
+use ::std::sync::LazyLock;
+
+/// Directory with one json file per prompt pack.
+/// The file name (minus extension) is the pack's id.
+const PROMPT_PACK_DIR: &str = "assets/prompts";
+
+/// A themed collection of prompts
+pub struct PromptPack {
+    pub info: PromptPackInfo,
+    pub prompts: Vec<PromptTemplate>,
+}
+
+/// Every prompt pack on disk, sorted by name.
+static PROMPT_PACKS: LazyLock<Vec<PromptPack>> = LazyLock::new(load_all);
+
+/// Reads the prompt packs from disk.
+/// You should call this once at the top level of the app,
+/// so any mistakes in the pack files are logged on boot instead of when the first game starts.
+pub fn init() {
+    log!("loaded {} prompt packs", PROMPT_PACKS.len());
+}
+
+/// Metadata for every pack, in the order they are listed at game setup.
+pub fn infos() -> Vec<PromptPackInfo> {
+    PROMPT_PACKS.iter().map(|p| p.info.clone()).collect()
+}
+
+/// Checks every pack the host picked was loaded.
+/// The error is a reason that can be shown to the host.
+pub fn check_ids(ids: &[PackId]) -> Result<(), String> {
+    match ids
+        .iter()
+        .find(|id| !PROMPT_PACKS.iter().any(|p| p.info.id == **id))
+    {
+        Some(id) => Err(format!("There's no prompt pack called {id:?}")),
+        None => Ok(()),
+    }
+}
+
+/// Shuffled deck of every prompt from the chosen packs.
+/// Prompts that show up in more than one pack are only added once, so they can't repeat.
+pub fn deck(ids: &[PackId], rng: &mut impl Rng) -> Vec<PromptTemplate> {
+    let mut seen = HashSet::new();
+    let mut deck = PROMPT_PACKS
+        .iter()
+        .filter(|pack| ids.contains(&pack.info.id))
+        .flat_map(|pack| pack.prompts.iter())
+        .filter(|p| seen.insert((&p.before, &p.after)))
+        .cloned()
+        .collect::<Vec<_>>();
+    shuffle(&mut deck, rng);
+    deck
+}
+
+/// Format of a prompt pack file
+#[derive(Deserialize)]
+struct PackFile {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    rating: ContentRating,
+    prompts: Vec<PromptFile>,
+}
+
+#[derive(Deserialize)]
+struct PromptFile {
+    /// e.g. "In case of emergency, remember ___"
+    template: String,
+    /// Suggested acronym length for this prompt
+    #[serde(default)]
+    letters: Option<Range<usize>>,
+}
+
+fn load_all() -> Vec<PromptPack> {
+    let Ok(entries) = std::fs::read_dir(PROMPT_PACK_DIR) else {
+        log!("No prompt packs found in {}", PROMPT_PACK_DIR);
+        return Vec::new();
+    };
+
+    let mut packs = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            load_pack(&path)
+        })
+        .collect::<Vec<_>>();
+    packs.sort_by(|a, b| a.info.name.cmp(&b.info.name));
+    packs
+}
+
+/// Invalid packs & prompts are logged & skipped.
+fn load_pack(path: &Path) -> Option<PromptPack> {
+    let id = path.file_stem()?.to_str()?.to_owned();
+    let json = std::fs::read_to_string(path).ok()?;
+    let file: PackFile = match serde_json::from_str(&json) {
+        Ok(file) => file,
+        Err(e) => {
+            log!("Discarding invalid prompt pack {}: {}", path.display(), e);
+            return None;
+        }
+    };
+
+    let prompts = file
+        .prompts
+        .into_iter()
+        .filter_map(|p| {
+            let Some((before, after)) = parse_prompt(&p.template) else {
+                log!("Discarding invalid prompt: {}", p.template);
+                return None;
+            };
+            let letters = p.letters.filter(|r| {
+                let valid = 0 < r.min && r.min <= r.max;
+                if !valid {
+                    log!("Ignoring invalid letter count for prompt: {}", p.template);
+                }
+                valid
+            });
+            Some(PromptTemplate {
+                before,
+                after,
+                letters,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Some(PromptPack {
+        info: PromptPackInfo {
+            id,
+            name: file.name,
+            description: file.description,
+            rating: file.rating,
+            prompt_count: prompts.len(),
+        },
+        prompts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::random::GameRng;
+
+    #[test]
+    fn default_pack_is_dealt_without_repeats() {
+        let deck = deck(
+            &[
+                DEFAULT_PROMPT_PACK.to_owned(),
+                DEFAULT_PROMPT_PACK.to_owned(),
+            ],
+            &mut GameRng::default(),
+        );
+        let classic = PROMPT_PACKS
+            .iter()
+            .find(|p| p.info.id == DEFAULT_PROMPT_PACK)
+            .unwrap();
+        assert!(!deck.is_empty());
+        assert_eq!(deck.len(), classic.prompts.len());
+    }
+}
@@ -1,15 +1,85 @@
 use ::rand::prelude::*;
+use ::rand_chacha::ChaCha8Rng;
+use ::serde::{Deserialize, Serialize};
+use ::uuid::Builder;
+
+/// Sets the seed for every new game, e.g. to reproduce a bug report.
+/// Otherwise each game gets a random seed.
+const SEED_VAR: &str = "ACRONYMIA_SEED";
+
+/// Source of every random decision in a game, so the same seed plays out the same game.
+/// Saved in snapshots, so a restored game carries on from the same point.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameRng(ChaCha8Rng);
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng(ChaCha8Rng::seed_from_u64(seed))
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::new(0)
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+/// The seed from the environment, if there is one
+pub fn initial_seed() -> u64 {
+    std::env::var(SEED_VAR)
+        .ok()
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or_else(random)
+}
 
 /// Apply a [Fisher-Yates shuffle](https://en.wikipedia.org/wiki/Fisher-Yates_shuffle) to a buffer
-pub fn shuffle<T>(slice: &mut [T]) {
+pub fn shuffle<T>(slice: &mut [T], rng: &mut impl Rng) {
     let n = slice.len();
     if n < 2 {
         return;
     }
 
-    let mut rng = rand::thread_rng();
     for i in 1..=(n - 1) {
         let j = rng.gen_range(0..=i);
         slice.swap(i, j);
     }
 }
+
+/// A v4 uuid, drawn from the game's rng
+pub fn uuid(rng: &mut impl Rng) -> String {
+    let mut bytes = [0; 16];
+    rng.fill_bytes(&mut bytes);
+    Builder::from_random_bytes(bytes).into_uuid().to_string()
+}
+
+/// A v4 uuid that can't be worked out from the game's seed, drawn from the OS's randomness.
+/// For secrets like submission tokens, so whoever knows the seed still can't tell who wrote what.
+pub fn secret_uuid() -> String {
+    uuid(&mut rand::thread_rng())
+}
+
+/// Generates a random code of uppercase letters, e.g. for identifying a room
+pub fn room_code(length: usize) -> String {
+    let mut rng = rand::thread_rng();
+    (0..length)
+        .map(|_| char::from(rng.gen_range(b'A'..=b'Z')))
+        .collect()
+}
@@ -0,0 +1,141 @@
+use super::types::*;
+use crate::extensions::ResultExt;
+use ::leptos::logging::log;
+use ::serde::{Deserialize, Serialize};
+use ::std::path::{Path, PathBuf};
+use ::tokio::sync::mpsc;
+use ::tokio::task::spawn_blocking;
+use ::tokio::time::{Duration, Instant};
+
+/// Directory where snapshots get written, one json file per room.
+/// Persistence is disabled unless this environment variable is set.
+const SNAPSHOT_DIR_VAR: &str = "ACRONYMIA_SNAPSHOT_DIR";
+
+/// Everything needed to pick a game back up after a server restart.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub state: GameState,
+    /// The timer that was running, and how much time it had left.
+    pub timer: Option<(TimerTag, Duration)>,
+}
+
+fn snapshot_dir() -> Option<PathBuf> {
+    std::env::var_os(SNAPSHOT_DIR_VAR).map(PathBuf::from)
+}
+
+fn snapshot_path(dir: &Path, code: &str) -> PathBuf {
+    dir.join(format!("{code}.json"))
+}
+
+/// What the room's saver should do next
+enum Write {
+    Save(String),
+    Remove,
+}
+
+/// Saves a room's snapshots in the background, so the state thread never waits on the disk.
+/// Only the latest snapshot matters, so any that pile up during a write are skipped.
+/// No-op if persistence is disabled.
+pub struct Saver {
+    sender: Option<mpsc::UnboundedSender<Write>>,
+}
+
+impl Saver {
+    pub fn new(code: &str) -> Self {
+        let Some(dir) = snapshot_dir() else {
+            return Saver { sender: None };
+        };
+        let path = snapshot_path(&dir, code);
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(mut next) = receiver.recv().await {
+                while let Ok(newer) = receiver.try_recv() {
+                    next = newer;
+                }
+                let (dir, path) = (dir.clone(), path.clone());
+                spawn_blocking(move || write(&dir, &path, next))
+                    .await
+                    .ok_or_log();
+            }
+        });
+        Saver {
+            sender: Some(sender),
+        }
+    }
+
+    /// Writes the room's game state to disk
+    pub fn save(&self, state: &GameState) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        let now = Instant::now().into_std();
+
+        let snapshot = SnapshotRef {
+            state,
+            timer: state.timer.tag().cloned().zip(state.timer.remaining(now)),
+        };
+
+        if let Some(json) = serde_json::to_string(&snapshot).ok_or_log() {
+            _ = sender.send(Write::Save(json));
+        }
+    }
+
+    /// Deletes the room's snapshot, e.g. when the room is garbage collected.
+    /// Any save that's still in progress finishes first.
+    pub fn remove(self) {
+        if let Some(sender) = &self.sender {
+            _ = sender.send(Write::Remove);
+        }
+    }
+}
+
+fn write(dir: &Path, path: &Path, write: Write) {
+    match write {
+        Write::Save(json) => {
+            // write then rename, so a crash mid-write can't leave a truncated snapshot behind
+            let tmp = path.with_extension("json.tmp");
+            std::fs::create_dir_all(dir)
+                .and_then(|()| std::fs::write(&tmp, json))
+                .and_then(|()| std::fs::rename(&tmp, path))
+                .ok_or_log();
+        }
+        Write::Remove => {
+            if path.exists() {
+                std::fs::remove_file(path).ok_or_log();
+            }
+        }
+    }
+}
+
+/// Reads every snapshot on disk.
+/// Invalid snapshots are logged & skipped.
+pub fn load_all() -> Vec<(RoomCode, Snapshot)> {
+    let Some(entries) = snapshot_dir().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            let code = path.file_stem()?.to_str()?.to_owned();
+            let json = std::fs::read_to_string(&path).ok_or_log()?;
+            match serde_json::from_str(&json) {
+                Ok(snapshot) => Some((code, snapshot)),
+                Err(e) => {
+                    log!("Discarding invalid snapshot {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Borrowed version of `Snapshot`, so saving doesn't require cloning the game state.
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+    state: &'a GameState,
+    timer: Option<(TimerTag, Duration)>,
+}
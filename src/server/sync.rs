@@ -1,64 +1,356 @@
+use super::engine::{self, Effect, Event};
+use super::game_log::GameLog;
+use super::random::room_code;
+use super::snapshot::{self, Saver};
 use super::types::*;
 use ::leptos::logging::log;
-use ::std::sync::OnceLock;
+use ::std::collections::HashMap;
+use ::std::sync::{Arc, LazyLock, Mutex as SyncMutex};
 use ::tokio::sync::{broadcast, mpsc, Mutex};
+use ::tokio::time::{sleep, timeout_at, Duration, Instant};
 
-pub struct Global {
-    mailbox_sender: mpsc::Sender<(SessionId, ClientMessage)>,
+/// Shortest time a room can sit without any connected players before it is garbage collected.
+/// The grace period lets players refresh the page without losing their game,
+/// and gives clients time to reconnect after a server restart.
+/// Rooms with a longer reconnect grace period wait that long instead, see `idle_timeout`.
+const ROOM_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Number of letters in a room code.
+const ROOM_CODE_LENGTH: usize = 4;
+
+/// Write-side of a channel to a single web socket.
+/// Used for messages that only one client should see.
+pub type Outbox = mpsc::Sender<ServerMessage>;
+
+/// Messages delivered to a room's state thread
+pub enum Mail {
+    /// A socket opened. Private messages for that session go through the outbox.
+    Open(SessionId, Outbox),
+    /// Sent by the client on the other end of the socket
+    Client(SessionId, ClientMessage),
+    /// Scheduled by the engine, e.g. when a timer runs out
+    Wake(Event),
+}
+
+/// Everything owned by a single game.
+/// Each room has its own state thread, game state, sessions & broadcast channel.
+pub struct Room {
+    pub code: RoomCode,
+    mailbox_sender: mpsc::Sender<Mail>,
     broadcast_sender: broadcast::Sender<ServerMessage>,
-    state: Mutex<GameState>,
+    pub state: Mutex<GameState>,
 }
 
-pub static GLOBAL: OnceLock<Global> = OnceLock::new();
+/// Registry of all the active rooms, keyed by room code.
+static ROOMS: LazyLock<SyncMutex<HashMap<RoomCode, Arc<Room>>>> =
+    LazyLock::new(|| SyncMutex::new(HashMap::new()));
 
-/// This is the read-side of a channel which receives messages from the state thread.
-/// i.e. it "subscribes" to server updates messages.
-///
-/// # Panics
-/// Panics if `spawn_state_thread` has not been run yet.  
-pub fn subscribe() -> broadcast::Receiver<ServerMessage> {
-    GLOBAL.get().unwrap().broadcast_sender.subscribe()
+impl Room {
+    /// This is the read-side of a channel which receives messages from the room's state thread.
+    /// i.e. it "subscribes" to server updates messages.
+    pub fn subscribe(&self) -> broadcast::Receiver<ServerMessage> {
+        self.broadcast_sender.subscribe()
+    }
+
+    /// This is the write-side of a channel which messages the room's state thread.
+    /// i.e. it "mails" the server with messages.
+    pub fn mailer(&self) -> mpsc::Sender<Mail> {
+        self.mailbox_sender.clone()
+    }
+
+    /// Send a message to every client connected to this room.
+    pub fn broadcast(&self, message: ServerMessage) {
+        _ = self.broadcast_sender.send(message);
+    }
 }
 
-/// This is the write-side of a channel which messages the state thread.
-/// i.e. it "mails" the server with messages.
+/// Look up an active room by its code.
 ///
 /// # Panics
-/// Panics if `spawn_state_thread` has not been run yet.  
-pub fn mailer() -> mpsc::Sender<(SessionId, ClientMessage)> {
-    GLOBAL.get().unwrap().mailbox_sender.clone()
+/// Panics if the room registry lock is poisoned.
+pub fn room(code: &str) -> Option<Arc<Room>> {
+    ROOMS.lock().unwrap().get(code).cloned()
 }
 
+/// Creates a new room with a fresh game, and spawns its state thread.
+/// To send messages to the room, call `Room::mailer`.
+/// To receive (broadcast) messages from the room, call `Room::subscribe`.
+///
 /// # Panics
-/// Panics if `spawn_state_thread` has not been run yet.  
-pub fn state() -> &'static Mutex<GameState> {
-    &GLOBAL.get().unwrap().state
+/// Panics if the room registry lock is poisoned.
+pub fn create_room() -> RoomCode {
+    let code = {
+        let rooms = ROOMS.lock().unwrap();
+        loop {
+            let code = room_code(ROOM_CODE_LENGTH);
+            if !rooms.contains_key(&code) {
+                break code;
+            }
+        }
+    };
+
+    let state = game_state_init();
+    let game_log = GameLog::create(&code, state.seed, now());
+    let saver = Saver::new(&code);
+    open_room(code.clone(), state, game_log, saver);
+    log!("room {} created", code);
+    code
 }
 
+/// Reopens every room that was persisted before the server last shut down.
+/// Timers pick up with the time they had left, and players get the grace period to reconnect.
 /// You should only call this once at the top level of the app.
-/// Manages the application state, with message passing infrastructure.
-/// To send to messages to this thread call `mailer`.
-/// To receive (broadcast) messges from this thread, call `subscribe`.
-pub fn spawn_state_thread() {
-    use super::state::*;
+pub fn restore_rooms() {
+    for (code, snapshot) in snapshot::load_all() {
+        let game_log = GameLog::restore(&code, snapshot.timer.clone(), now());
+        let saver = Saver::new(&code);
+        let mut state = snapshot.state;
+        // restored before the room opens, so nothing else can get to the state first
+        let effects = engine::restore(&mut state, snapshot.timer, now());
+        save_changes(&effects, &state, &saver);
+        let room = open_room(code, state, game_log, saver);
+        // there's nobody connected yet, so this just schedules the timers
+        run_effects(effects, &room, &HashMap::new());
+        log!("room {} restored", room.code);
+    }
+}
 
-    tokio::spawn(async move {
-        let (mailbox_sender, mut receiver) = mpsc::channel(100);
-        let (broadcast_sender, _) = broadcast::channel(100);
-        let sender = broadcast_sender.clone();
-        _ = GLOBAL.set(Global {
-            mailbox_sender,
-            broadcast_sender,
-            state: Mutex::new(game_state_init()),
-        });
+/// # Panics
+/// Panics if the room registry lock is poisoned.
+fn open_room(code: RoomCode, state: GameState, game_log: GameLog, saver: Saver) -> Arc<Room> {
+    let (mailbox_sender, receiver) = mpsc::channel(100);
+    let (broadcast_sender, _) = broadcast::channel(100);
+    let room = Arc::new(Room {
+        code: code.clone(),
+        mailbox_sender,
+        broadcast_sender,
+        state: Mutex::new(state),
+    });
+    ROOMS.lock().unwrap().insert(code, room.clone());
+    spawn_state_thread(room.clone(), receiver, game_log, saver);
+    room
+}
 
+/// Manages the room's state, with message passing infrastructure.
+/// This is the driver for the game engine: it feeds events in, and carries out the effects.
+/// The thread exits, and the room is removed from the registry,
+/// once nobody has been connected for the `idle_timeout`.
+fn spawn_state_thread(
+    room: Arc<Room>,
+    mut receiver: mpsc::Receiver<Mail>,
+    game_log: GameLog,
+    saver: Saver,
+) {
+    tokio::spawn(async move {
         let mut sessions = Sessions::new();
+        let mut outboxes = HashMap::new();
+        // only sockets count as activity, scheduled events don't keep the room alive
+        let mut last_activity = Instant::now();
+
+        loop {
+            let next = if outboxes.is_empty() {
+                let idle_timeout = idle_timeout(&room.state.lock().await.config);
+                match timeout_at(last_activity + idle_timeout, receiver.recv()).await {
+                    Ok(next) => next,
+                    // nobody has connected in a while
+                    Err(_) => break,
+                }
+            } else {
+                receiver.recv().await
+            };
+
+            let event = match next {
+                Some(Mail::Open(session_id, outbox)) => {
+                    last_activity = Instant::now();
+                    outboxes.insert(session_id, outbox);
+                    continue;
+                }
+                Some(Mail::Client(session_id, message)) => {
+                    last_activity = Instant::now();
+                    Event::Message(session_id, message)
+                }
+                Some(Mail::Wake(event)) => event,
+                None => break,
+            };
 
-        while let Some((session_id, message)) = receiver.recv().await {
-            let mut state = state().lock().await;
-            handle_message(session_id, message, &mut state, &mut sessions, &sender).await;
+            // the socket is closed, so there's nobody to reply to
+            let closed = match &event {
+                Event::Message(session_id, ClientMessage::Disconnect) => Some(session_id.clone()),
+                _ => None,
+            };
+
+            let mut state = room.state.lock().await;
+            let time = now();
+            game_log.record(&event, &state, time);
+            let effects = engine::apply(&mut state, &mut sessions, event, time);
+            if let Some(session_id) = closed {
+                outboxes.remove(&session_id);
+            }
+            save_changes(&effects, &state, &saver);
+            run_effects(effects, &room, &outboxes);
         }
 
-        log!("state thread closed");
+        close_room(&room.code);
+        saver.remove();
+        log!("room {} closed", room.code);
     });
 }
+
+/// Players who drop can come back until their grace period is up, so the room waits for them
+fn idle_timeout(config: &Config) -> Duration {
+    let grace = Duration::from_secs(config.timer_secs.reconnect_grace);
+    ROOM_IDLE_TIMEOUT.max(grace)
+}
+
+/// The engine works with std instants, which follow tokio's clock when it's paused in tests
+fn now() -> std::time::Instant {
+    Instant::now().into_std()
+}
+
+/// Snapshots the game, if the engine says it changed
+fn save_changes(effects: &[Effect], state: &GameState, saver: &Saver) {
+    if effects.iter().any(|e| matches!(e, Effect::Save)) {
+        saver.save(state);
+    }
+}
+
+fn run_effects(effects: Vec<Effect>, room: &Room, outboxes: &HashMap<SessionId, Outbox>) {
+    for effect in effects {
+        match effect {
+            Effect::Broadcast(message) => room.broadcast(message),
+            Effect::Reply(session_id, message) => {
+                if let Some(outbox) = outboxes.get(&session_id) {
+                    send(outbox, message);
+                }
+            }
+            // stops quietly if the room closes in the meantime
+            Effect::Schedule(delay, event) => {
+                let mailer = room.mailer();
+                tokio::spawn(async move {
+                    sleep(delay).await;
+                    _ = mailer.send(Mail::Wake(event)).await;
+                });
+            }
+            // see `save_changes`, which has the state to save
+            Effect::Save => {}
+        }
+    }
+}
+
+/// Never blocks the state thread.
+/// If a client is so far behind that its outbox is full, the message is dropped.
+fn send(outbox: &Outbox, message: ServerMessage) {
+    if let Err(e) = outbox.try_send(message) {
+        log!("dropped private message: {}", e);
+    }
+}
+
+fn close_room(code: &str) {
+    if let Ok(mut rooms) = ROOMS.lock() {
+        rooms.remove(code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::testing::{joining, letters, session};
+
+    /// Mails the room as if from a socket, then waits for the state thread to handle it
+    async fn send(room: &Room, id: &str, message: ClientMessage) {
+        room.mailer()
+            .send(Mail::Client(session(id), message))
+            .await
+            .unwrap();
+        sleep(Duration::from_millis(1)).await;
+    }
+
+    /// Opens a room with three players, and starts the game
+    async fn start_game(config: Config) -> (Arc<Room>, Vec<mpsc::Receiver<ServerMessage>>) {
+        let room = room(&create_room()).unwrap();
+        let mut inboxes = Vec::new();
+        for id in ["a", "b", "c"] {
+            let (outbox, inbox) = mpsc::channel(100);
+            inboxes.push(inbox);
+            room.mailer()
+                .send(Mail::Open(session(id), outbox))
+                .await
+                .unwrap();
+            for message in joining(id) {
+                send(&room, id, message).await;
+            }
+        }
+        send(&room, "a", ClientMessage::UpdateConfig(config)).await;
+        send(&room, "a", ClientMessage::StartGame).await;
+        (room, inboxes)
+    }
+
+    async fn step(room: &Room) -> GameStep {
+        room.state.lock().await.step.clone()
+    }
+
+    fn submission_secs() -> Duration {
+        Duration::from_secs(Config::default().timer_secs.submission)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rooms_wait_out_the_reconnect_grace() {
+        let mut config = Config::default();
+        config.timer_secs.reconnect_grace = TimerSecs::MAX;
+        let code = {
+            let (game, _inboxes) = start_game(config).await;
+            for id in ["a", "b", "c"] {
+                send(&game, id, ClientMessage::Disconnect).await;
+            }
+            game.code.clone()
+        };
+
+        sleep(ROOM_IDLE_TIMEOUT * 2).await;
+        assert!(room(&code).is_some());
+        sleep(Duration::from_secs(TimerSecs::MAX)).await;
+        assert!(room(&code).is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timers_advance_the_game() {
+        let (room, _inboxes) = start_game(Config::default()).await;
+        assert_eq!(step(&room).await, GameStep::Submission);
+
+        sleep(submission_secs() / 2).await;
+        assert_eq!(step(&room).await, GameStep::Submission);
+        sleep(submission_secs()).await;
+        assert_eq!(step(&room).await, GameStep::Judging);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stopped_timers_never_fire() {
+        let (room, _inboxes) = start_game(Config::default()).await;
+        send(&room, "a", ClientMessage::StopTimer).await;
+
+        sleep(submission_secs() * 2).await;
+        assert_eq!(step(&room).await, GameStep::Submission);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn superseded_timers_never_fire() {
+        // judging outlasts the submission timer, which is still scheduled
+        let mut config = Config::default();
+        config.timer_secs.judging = config.timer_secs.submission * 2;
+        let (room, _inboxes) = start_game(config).await;
+
+        let acronym = {
+            let state = room.state.lock().await;
+            state.rounds.last().unwrap().prompt.acronym.clone()
+        };
+        for id in ["b", "c"] {
+            send(&room, id, ClientMessage::SubmitAcronym(letters(&acronym))).await;
+        }
+        assert_eq!(step(&room).await, GameStep::Judging);
+
+        sleep(submission_secs() * 3 / 2).await;
+        assert_eq!(step(&room).await, GameStep::Judging);
+        // the judging timer that replaced it still fires
+        sleep(submission_secs()).await;
+        assert_eq!(step(&room).await, GameStep::Submission);
+    }
+}
@@ -0,0 +1,105 @@
+//! Fixtures shared by the server's tests
+use super::engine::{apply, Effect, Event};
+use super::random::secret_uuid;
+use super::types::*;
+use ::std::path::{Path, PathBuf};
+use ::std::time::Instant;
+
+/// Each player has a single session, named after them
+pub fn session(id: &str) -> SessionId {
+    SessionId(id.to_owned())
+}
+
+/// One letter per word, which always fits the acronym
+pub fn letters(acronym: &str) -> Submission {
+    acronym.chars().map(String::from).collect()
+}
+
+/// What a player sends to join the game, using their id as their name
+pub fn joining(id: &str) -> [ClientMessage; 2] {
+    let name = id.to_owned();
+    [
+        ClientMessage::Connect(id.to_owned()),
+        ClientMessage::JoinGame { name },
+    ]
+}
+
+/// Something that runs events through the engine, like a `Table`
+pub trait Play {
+    fn state(&self) -> &GameState;
+
+    fn apply(&mut self, event: Event) -> Vec<Effect>;
+
+    fn send(&mut self, id: &str, message: ClientMessage) -> Vec<Effect> {
+        self.apply(Event::Message(session(id), message))
+    }
+
+    fn join(&mut self, id: &str) {
+        for message in joining(id) {
+            self.send(id, message);
+        }
+    }
+
+    /// Everyone except the judge submits one letter per word
+    fn submit_all(&mut self) {
+        let acronym = self.state().rounds.last().unwrap().prompt.acronym.clone();
+        let judge = self.state().judge_id();
+        let players = self.state().active_players().cloned().collect::<Vec<_>>();
+        for id in players.iter().filter(|id| Some(*id) != judge.as_ref()) {
+            self.send(id, ClientMessage::SubmitAcronym(letters(&acronym)));
+        }
+    }
+}
+
+/// A room without a driver. The clock only moves when a test moves it.
+pub struct Table {
+    pub state: GameState,
+    pub sessions: Sessions,
+    pub now: Instant,
+}
+
+impl Table {
+    pub fn new(players: &[&str], config: Config) -> Self {
+        let mut table = Table {
+            state: GameState::default(),
+            sessions: Sessions::new(),
+            now: Instant::now(),
+        };
+        for id in players {
+            table.join(id);
+        }
+        table.send(players[0], ClientMessage::UpdateConfig(config));
+        table
+    }
+}
+
+impl Play for Table {
+    fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    fn apply(&mut self, event: Event) -> Vec<Effect> {
+        apply(&mut self.state, &mut self.sessions, event, self.now)
+    }
+}
+
+/// A directory of its own under the system's temp dir, removed once the test is done
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("acronymia-test-{}", secret_uuid()));
+        std::fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -1,14 +1,13 @@
 mod sessions;
 pub use self::sessions::Sessions;
-use super::random::shuffle;
+use super::random::{initial_seed, secret_uuid, shuffle, GameRng};
 use crate::constants::*;
 pub use crate::types::*;
+use crate::validation::is_letter;
 use ::leptos::logging::log;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use tokio::{
-    sync::oneshot,
-    time::{Duration, Instant},
-};
+use std::time::{Duration, Instant};
 pub use uuid::Uuid;
 
 /// Index into the rotation vector
@@ -17,93 +16,198 @@ type JudgeId = usize;
 /// Server game state
 /// The idea is to make the state very normalized.
 /// e.g. Determining who the current judge is can be a function, that looks at the last item of the rounds vector. (instead of another field for meta data like that)
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GameState {
     pub step: GameStep,
     /// Player information
     pub players: HashMap<PlayerId, ServerPlayer>,
     /// Player ids in order they will be judge
     pub rotation: Vec<PlayerId>,
+    /// Runs the game. The first player to join, until they hand it to someone else.
+    pub host: Option<PlayerId>,
+    /// Spectators, who can join at any step
+    pub audience: Vec<Player>,
     pub rounds: Vec<Round>,
+    /// Authors of the current round's submissions, in the order they are shown.
+    /// Clients only ever see the submission tokens, until the winner is revealed.
     pub shuffled_submissions: Vec<(PlayerId, Submission)>,
+    /// Not serializable, snapshots store the remaining time separately
+    #[serde(skip)]
     pub timer: Timer,
+    /// Bumped whenever the timer is cancelled or replaced.
+    /// A timeout only fires if the generation it was armed in is still current.
+    #[serde(skip)]
+    pub timer_generation: u64,
     pub config: Config,
-    pub prompts: Vec<(String, String)>,
+    /// Bumped for every patch sent to clients, so they can detect missed patches
+    pub version: u64,
+    /// Deck of prompts, drawn from the chosen prompt packs when the game starts.
+    /// Each round takes the next one.
+    pub prompts: Vec<PromptTemplate>,
+    /// Written by players during setup, and dealt into the prompt deck when the game starts
+    pub player_prompts: Vec<PlayerPrompt>,
+    /// Logged when the game starts, so the game can be reproduced
+    pub seed: u64,
+    /// Every random decision in the game comes from here
+    pub rng: GameRng,
 }
 
-#[derive(Default, Debug)]
+/// A prompt from the deck, waiting to be played
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PromptTemplate {
+    pub before: String,
+    pub after: String,
+    /// Overrides the configured acronym length, for prompts that suit a particular length
+    pub letters: Option<Range<usize>>,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Round {
+    /// Unused in head to head mode
     pub judge: JudgeId,
     pub prompt: Prompt,
     pub winner: Option<PlayerId>,
     pub submissions: HashMap<PlayerId, Submission>,
+    /// Maps each voter to the player they voted for, in head to head mode
+    pub votes: HashMap<PlayerId, PlayerId>,
+    /// Set once voting ends, after which the tallies are public
+    pub votes_closed: bool,
+    /// Maps each audience member to the player they voted for
+    pub audience_votes: HashMap<PlayerId, PlayerId>,
+    /// Anonymous handle for each player's submission
+    pub tokens: HashMap<PlayerId, SubmissionToken>,
 }
 
-#[derive(Debug)]
+impl Round {
+    /// Number of votes received per player
+    pub fn vote_tallies(&self) -> HashMap<&PlayerId, usize> {
+        let mut tallies = HashMap::new();
+        for candidate in self.votes.values() {
+            *tallies.entry(candidate).or_default() += 1;
+        }
+        tallies
+    }
+
+    /// The players with the most audience votes, if there were any votes at all
+    pub fn audience_favorites(&self) -> Vec<&PlayerId> {
+        let mut tallies = HashMap::new();
+        for candidate in self.audience_votes.values() {
+            *tallies.entry(candidate).or_insert(0) += 1;
+        }
+        let most_votes = tallies.values().copied().max().unwrap_or(0);
+        tallies
+            .into_iter()
+            .filter(|(_, votes)| *votes == most_votes)
+            .map(|(candidate, _)| candidate)
+            .collect()
+    }
+
+    /// The submission token for a player, made the first time they submit
+    pub fn token(&mut self, id: &PlayerId) -> SubmissionToken {
+        self.tokens
+            .entry(id.clone())
+            .or_insert_with(secret_uuid)
+            .clone()
+    }
+
+    /// Who wrote the submission, if the token is from this round
+    pub fn author_of(&self, token: &str) -> Option<&PlayerId> {
+        self.tokens
+            .iter()
+            .find(|(_, t)| *t == token)
+            .map(|(id, _)| id)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ServerPlayer {
     /// If a player needs to leave midgame, we leave them in place to not break the judge rotation
     /// and allow showing their end of game score.
     pub quit: bool,
     pub id: PlayerId,
     pub name: String,
+    /// Whether the player has a socket open.
+    /// Nobody is connected when a room is restored, until they reconnect.
+    #[serde(default)]
+    pub connected: bool,
+    /// When the player last disconnected.
+    /// They quit if they stay offline for the grace period.
+    #[serde(skip)]
+    pub offline_since: Option<Instant>,
+    /// Played by the server, see `bots`
+    #[serde(default)]
+    pub bot: bool,
 }
 
+/// Keeps track of the running timer, for the clients' countdowns and for snapshots.
+/// The driver is the one that actually waits, see `Effect::Schedule`.
 #[derive(Debug, Default)]
 pub struct Timer(Option<TimerFields>);
 
 #[derive(Debug)]
 struct TimerFields {
     started_at: Instant,
-    cancellation: oneshot::Sender<()>,
+    duration: Duration,
     tag: TimerTag,
 }
 
 impl Timer {
-    pub fn duration(tag: &TimerTag) -> Duration {
-        Duration::new(tag.secs(), 0)
+    pub fn duration(tag: &TimerTag, config: &Config) -> Duration {
+        Duration::new(tag.secs(config), 0)
     }
 
-    pub fn new(started_at: Instant, cancellation: oneshot::Sender<()>, tag: TimerTag) -> Self {
+    pub fn new(started_at: Instant, duration: Duration, tag: TimerTag) -> Self {
         Self(Some(TimerFields {
             started_at,
-            cancellation,
+            duration,
             tag,
         }))
     }
 
-    pub fn elapsed(&self) -> Option<Duration> {
-        self.0.as_ref().map(|f| f.started_at.elapsed())
+    pub fn tag(&self) -> Option<&TimerTag> {
+        self.0.as_ref().map(|f| &f.tag)
     }
 
-    pub fn remaining_secs(&self) -> Option<u64> {
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
         self.0.as_ref().and_then(|t| {
-            let elapsed = t.started_at.elapsed();
-            let duration = Self::duration(&t.tag);
-            if elapsed < duration {
-                let diff = duration - elapsed;
-                // clippy recommended the From instance for bool to u64
-                let rounded_sec = u64::from(diff.subsec_nanos() >= 500_000_000);
-                Some(diff.as_secs() + rounded_sec)
+            let elapsed = now.saturating_duration_since(t.started_at);
+            if elapsed < t.duration {
+                t.duration.checked_sub(elapsed)
             } else {
                 None
             }
         })
     }
 
+    pub fn remaining_secs(&self, now: Instant) -> Option<u64> {
+        self.remaining(now).map(|diff| {
+            // clippy recommended the From instance for bool to u64
+            let rounded_sec = u64::from(diff.subsec_nanos() >= 500_000_000);
+            diff.as_secs() + rounded_sec
+        })
+    }
+
     pub fn cancel(&mut self) {
-        if let Some(fields) = self.0.take() {
-            _ = fields.cancellation.send(());
-        }
+        self.0 = None;
     }
 }
 
 impl GameState {
-    pub fn next_prompt(&self) -> Prompt {
-        use crate::server::letter_bag::random_initialism;
-        let acronym = random_initialism(&self.config.letters_per_acronym);
-        let n = self.rounds.len();
-        let (before, after) = if let Some(p) = self.prompts.get(n) {
-            (p.0.clone(), p.1.clone())
+    pub fn next_prompt(&mut self) -> Prompt {
+        use crate::server::letter_bag::{random_acronym, random_initialism};
+        let generate = match self.config.acronym_style {
+            AcronymStyle::Initialism => random_initialism,
+            AcronymStyle::Pronounceable => random_acronym,
+        };
+        let template = self.prompts.get(self.rounds.len());
+        let letters = template
+            .and_then(|t| t.letters.as_ref())
+            .unwrap_or(&self.config.letters_per_acronym);
+        let acronym = generate(letters, &self.config.letter_distribution, &mut self.rng);
+        let (before, after) = if let Some(t) = template {
+            (t.before.clone(), t.after.clone())
         } else {
             ("What is ".to_owned(), " ?".to_owned())
         };
@@ -114,6 +218,25 @@ impl GameState {
         }
     }
 
+    /// Shuffles the player prompts into the front of the deck, amongst the prompts for the rounds
+    /// that will actually be played. Otherwise they could end up too deep in the deck to ever come up.
+    pub fn deal_player_prompts(&mut self) {
+        let player_prompts = self
+            .player_prompts
+            .iter()
+            .map(|p| PromptTemplate {
+                before: p.before.clone(),
+                after: p.after.clone(),
+                letters: None,
+            })
+            .collect::<Vec<_>>();
+        let n = std::cmp::max(player_prompts.len(), self.total_rounds());
+        self.prompts.splice(0..0, player_prompts);
+
+        let n = std::cmp::min(n, self.prompts.len());
+        shuffle(&mut self.prompts[..n], &mut self.rng);
+    }
+
     pub fn current_judge(&self) -> Option<JudgeId> {
         self.rounds.last().as_ref().map(|r| r.judge)
     }
@@ -134,19 +257,143 @@ impl GameState {
         0
     }
 
+    pub fn is_player(&self, id: &PlayerId) -> bool {
+        self.players.get(id).is_some_and(|p| !p.quit)
+    }
+
+    /// Whether someone else in the game or audience already goes by that name
+    pub fn is_name_taken(&self, id: &PlayerId, name: &str) -> bool {
+        let same = |other: &str| other.trim().eq_ignore_ascii_case(name.trim());
+        let players = self
+            .players
+            .values()
+            .any(|p| !p.quit && p.id != *id && same(&p.name));
+        let audience = self.audience.iter().any(|p| p.id != *id && same(&p.name));
+        players || audience
+    }
+
+    pub fn is_bot(&self, id: &PlayerId) -> bool {
+        self.players.get(id).is_some_and(|p| p.bot)
+    }
+
+    /// Bots still in the game, in rotation order
+    pub fn active_bots(&self) -> impl Iterator<Item = &PlayerId> {
+        self.active_players().filter(|id| self.is_bot(id))
+    }
+
+    pub fn is_host(&self, id: &PlayerId) -> bool {
+        self.host.as_ref() == Some(id)
+    }
+
+    /// Who takes over, if the host leaves.
+    /// The next player in the rotation after the host, who is still connected and isn't a bot.
+    pub fn next_host(&self) -> Option<PlayerId> {
+        let n = self.rotation.len();
+        let start = self
+            .host
+            .as_ref()
+            .and_then(|h| self.rotation.iter().position(|id| id == h))
+            .unwrap_or(n.saturating_sub(1));
+        (1..=n)
+            .map(|offset| &self.rotation[(start + offset) % n])
+            .find(|id| {
+                !self.is_host(id)
+                    && !self.is_bot(id)
+                    && self.is_player(id)
+                    && self.players[*id].connected
+            })
+            .cloned()
+    }
+
+    /// Picks the round winner. Usually the judge, but the host fills in if the judge left.
+    pub fn acting_judge(&self) -> Option<PlayerId> {
+        match self.judge_id() {
+            Some(judge) if self.is_player(&judge) => Some(judge),
+            Some(_) => self.host.clone(),
+            None => None,
+        }
+    }
+
+    pub fn is_audience(&self, id: &PlayerId) -> bool {
+        self.audience.iter().any(|p| p.id == *id)
+    }
+
+    pub fn is_head_to_head(&self) -> bool {
+        self.config.mode == GameMode::HeadToHead
+    }
+
+    /// Players still in the game, in rotation order
+    pub fn active_players(&self) -> impl Iterator<Item = &PlayerId> {
+        self.rotation.iter().filter(|id| self.is_player(id))
+    }
+
+    /// Everyone still in the game has submitted.
+    /// Everyone submits in head to head mode, otherwise everyone but the acting judge submits.
+    pub fn all_submitted(&self) -> bool {
+        let Some(round) = self.rounds.last() else {
+            return false;
+        };
+        let judge = self.acting_judge();
+        self.active_players()
+            .filter(|id| Some(*id) != judge.as_ref())
+            .all(|id| round.submissions.contains_key(id))
+    }
+
+    /// Everyone still in the game has voted, in head to head mode.
+    /// Nobody can vote for themselves, so players whose submission is the only one sit it out.
+    pub fn all_voted(&self) -> bool {
+        let Some(round) = self.rounds.last() else {
+            return false;
+        };
+        self.active_players()
+            .filter(|id| round.submissions.keys().any(|author| author != *id))
+            .all(|id| round.votes.contains_key(id))
+    }
+
+    /// Players who quit no longer count towards rounds per judge
+    pub fn total_rounds(&self) -> usize {
+        self.config
+            .game_length
+            .total_rounds(self.active_players().count())
+    }
+
+    /// The game ends once the last round is played,
+    /// or as soon as someone reaches the target score.
+    pub fn is_game_over(&self) -> bool {
+        if self.rounds.len() >= self.total_rounds() {
+            return true;
+        }
+
+        self.config.target_score.is_some_and(|target| {
+            self.scores()
+                .first()
+                .is_some_and(|(_, score)| *score >= target)
+        })
+    }
+
+    /// Restarts the rng, so everything from here on can be replayed from the seed
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = GameRng::new(seed);
+    }
+
+    /// Stops the timer, and retires its generation.
+    /// A timeout that's already scheduled will see that it's stale.
     pub fn cancel_timer(&mut self) {
         self.timer.cancel();
+        self.timer_generation += 1;
     }
 
     pub fn shuffle_current_round_submissions(&mut self) {
         if let Some(round) = self.rounds.last() {
-            let mut subs = round
-                .submissions
+            // start from the rotation, since the hashmap's order changes from run to run
+            let mut subs = self
+                .rotation
                 .iter()
-                .map(|(key, value)| (key.clone(), value.clone()))
+                .filter_map(|id| round.submissions.get(id).map(|s| (id.clone(), s.clone())))
                 .collect::<Vec<_>>();
 
-            shuffle(&mut subs);
+            shuffle(&mut subs, &mut self.rng);
 
             self.shuffled_submissions = subs;
         } else {
@@ -154,23 +401,72 @@ impl GameState {
         }
     }
 
+    /// Rounds in the second half are worth double, if that rule is enabled
+    pub fn round_multiplier(&self, round_index: usize) -> i64 {
+        if self.config.scoring.double_points_second_half && 2 * round_index >= self.total_rounds() {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Points lost for characters that aren't letters, if that rule is enabled
+    pub fn penalty(&self, sub: &Submission) -> i64 {
+        if self.config.scoring.character_penalty {
+            submission_penalty(sub, &self.config.letter_distribution)
+        } else {
+            0
+        }
+    }
+
+    /// Points earned from audience votes, per player
+    pub fn audience_bonuses(&self) -> HashMap<&PlayerId, i64> {
+        let mut bonuses = HashMap::new();
+        let bonus = self.config.scoring.audience_bonus;
+        if bonus > 0 {
+            for (i, round) in self.rounds.iter().enumerate() {
+                for id in round.audience_favorites() {
+                    insert_or_add(&mut bonuses, id, bonus * self.round_multiplier(i));
+                }
+            }
+        }
+        bonuses
+    }
+
     pub fn scores(&self) -> Vec<(PlayerName, i64)> {
         let mut score_map = HashMap::new();
-        for round in &self.rounds {
+        for (i, round) in self.rounds.iter().enumerate() {
             #[allow(clippy::cast_possible_wrap)]
-            let points = round.prompt.acronym.len() as i64;
-            if let Some(winner) = &round.winner {
-                let penalty = submission_penalty(&round.submissions[winner]);
-                let points = std::cmp::max(points + penalty, 0);
+            let points = round.prompt.acronym.chars().count() as i64;
+            let multiplier = self.round_multiplier(i);
+            if self.is_head_to_head() {
+                // every vote is worth as much as a judge's pick
+                for (candidate, votes) in round.vote_tallies() {
+                    let penalty = self.penalty(&round.submissions[candidate]);
+                    #[allow(clippy::cast_possible_wrap)]
+                    let votes = votes as i64;
+                    let points = std::cmp::max(points + penalty, 0) * votes * multiplier;
+                    insert_or_add(&mut score_map, candidate, points);
+                }
+            } else if let Some(winner) = &round.winner {
+                let penalty = self.penalty(&round.submissions[winner]);
+                let points = std::cmp::max(points + penalty, 0) * multiplier;
                 insert_or_add(&mut score_map, winner, points);
-            } else {
+            } else if self.config.scoring.judge_timeout_penalty {
                 // The judge is penalized for a timeout.
                 // This is because any round where you don't select a winner
                 // you've denied all of your peers any points.
                 // Penalizing the judge fixes this issue from a "game theory" perspective
-                insert_or_add(&mut score_map, &self.rotation[round.judge], -points);
+                insert_or_add(
+                    &mut score_map,
+                    &self.rotation[round.judge],
+                    -points * multiplier,
+                );
             }
         }
+        for (id, bonus) in self.audience_bonuses() {
+            insert_or_add(&mut score_map, id, bonus);
+        }
         let mut scores = Vec::with_capacity(self.rotation.len());
         for id in &self.rotation {
             let score = score_map.get(id).map_or(0, |s| *s);
@@ -182,39 +478,146 @@ impl GameState {
         scores
     }
 
-    pub fn to_client_state(&self) -> ClientGameState {
-        let judge = self
-            .current_judge()
-            .and_then(|j| self.rotation.get(j))
-            .cloned();
+    /// The current judge's player id. Always `None` in head to head mode.
+    pub fn judge_id(&self) -> Option<PlayerId> {
+        if self.is_head_to_head() {
+            None
+        } else {
+            self.current_judge()
+                .and_then(|j| self.rotation.get(j))
+                .cloned()
+        }
+    }
+
+    /// The current round's submissions, in the order they are shown, without their authors
+    pub fn anonymous_submissions(&self) -> Vec<(SubmissionToken, Submission)> {
+        let Some(round) = self.rounds.last() else {
+            return Vec::new();
+        };
+        self.shuffled_submissions
+            .iter()
+            .filter_map(|(id, sub)| Some((round.tokens.get(id)?.clone(), sub.clone())))
+            .collect()
+    }
+
+    /// Who wrote each submission.
+    /// Empty until the round winner is picked, or voting closes.
+    pub fn revealed_authors(&self) -> Vec<(SubmissionToken, PlayerId)> {
+        match self.rounds.last() {
+            Some(round) if round.winner.is_some() || round.votes_closed => self
+                .shuffled_submissions
+                .iter()
+                .filter_map(|(id, _)| Some((round.tokens.get(id)?.clone(), id.clone())))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
 
-        let empty_vec = Vec::new();
-        let submissions = {
-            if self.step == GameStep::Judging {
-                self.shuffled_submissions.clone()
+    /// Votes per submission, in the order submissions are shown.
+    /// Empty until voting closes.
+    pub fn revealed_vote_tallies(&self) -> Vec<(SubmissionToken, usize)> {
+        match self.rounds.last() {
+            Some(round) if round.votes_closed => {
+                let tallies = round.vote_tallies();
+                self.shuffled_submissions
+                    .iter()
+                    .filter_map(|(id, _)| {
+                        let votes = tallies.get(id).copied().unwrap_or(0);
+                        Some((round.tokens.get(id)?.clone(), votes))
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Audience bonus per player, by name, in rotation order
+    pub fn audience_bonuses_by_name(&self) -> Vec<(PlayerName, i64)> {
+        let bonuses = self.audience_bonuses();
+        self.rotation
+            .iter()
+            .map(|id| {
+                let bonus = bonuses.get(id).copied().unwrap_or(0);
+                (self.players[id].name.clone(), bonus)
+            })
+            .collect()
+    }
+
+    pub fn round_counter(&self) -> String {
+        let n = self.rounds.len();
+        let mut counter = format!("Round {}/{}", n, self.total_rounds());
+        if let Some(target) = self.config.target_score {
+            counter = format!("{counter}, or first to {target} points");
+        }
+        if n > 0 && self.round_multiplier(n - 1) > 1 {
+            counter = format!("{counter} (double points!)");
+        }
+        counter
+    }
+
+    /// The viewer is who the state is sent to, so they can recognize their own submission
+    pub fn to_client_state(&self, viewer: Option<&PlayerId>, now: Instant) -> ClientGameState {
+        let judge = self.judge_id();
+
+        let (submissions, authors) =
+            if self.step == GameStep::Judging || self.step == GameStep::Voting {
+                (self.anonymous_submissions(), self.revealed_authors())
             } else {
-                empty_vec
+                (Vec::new(), Vec::new())
+            };
+
+        let own_submission = match (self.rounds.last(), viewer) {
+            (Some(round), Some(id)) if self.step != GameStep::Results => {
+                round.tokens.get(id).cloned()
             }
+            _ => None,
         };
 
+        // tallies are revealed along with the round winner
+        let vote_tallies = self.revealed_vote_tallies();
+
         let scores = if self.step == GameStep::Results {
             self.scores()
         } else {
             Vec::new()
         };
 
-        let round_counter = format!("Round {}/{}", self.rounds.len(), 2 * self.rotation.len());
+        let audience_bonuses = if self.step == GameStep::Results {
+            self.audience_bonuses_by_name()
+        } else {
+            Vec::new()
+        };
 
         ClientGameState {
+            version: self.version,
             judge,
+            host: self.host.clone(),
+            offline: self
+                .active_players()
+                .filter(|id| !self.players[*id].connected)
+                .cloned()
+                .collect(),
+            bots: self.active_bots().cloned().collect(),
             submissions,
+            authors,
+            own_submission,
             scores,
-            round_counter,
+            audience_bonuses,
+            audience: self.audience.clone(),
+            audience_vote_count: self.rounds.last().map_or(0, |r| r.audience_votes.len()),
+            round_counter: self.round_counter(),
             config: self.config.clone(),
-            timer: self.timer.remaining_secs(),
+            player_prompts: if self.step == GameStep::Setup {
+                self.player_prompts.clone()
+            } else {
+                Vec::new()
+            },
+            timer: self.timer.remaining_secs(now),
             round_winner: self.rounds.last().and_then(|r| r.winner.clone()),
+            vote_tallies,
             step: self.step.clone(),
             submission_count: self.rounds.last().map_or(0, |r| r.submissions.len()),
+            vote_count: self.rounds.last().map_or(0, |r| r.votes.len()),
             prompt: self
                 .rounds
                 .last()
@@ -239,18 +642,15 @@ impl GameState {
     }
 }
 
-fn submission_penalty(sub: &Submission) -> i64 {
-    sub.iter().map(|w| word_penalty(w)).sum()
+fn submission_penalty(sub: &Submission, distribution: &LetterDistribution) -> i64 {
+    sub.iter().map(|w| word_penalty(w, distribution)).sum()
 }
 
-fn word_penalty(word: &str) -> i64 {
+fn word_penalty(word: &str, distribution: &LetterDistribution) -> i64 {
+    // letters like Ñ are fine, if they're part of the game's alphabet
     let violations = word
         .chars()
-        .filter(|c| {
-            let c = *c as u32;
-            // outside range of a-z & A-Z
-            c < 65 || (c > 91 && c < 97) || c > 122
-        })
+        .filter(|c| !is_letter(*c, distribution))
         .count();
 
     #[allow(clippy::cast_possible_wrap)]
@@ -270,30 +670,23 @@ pub fn game_state_init() -> GameState {
     let mut state = if DEV_MODE {
         demo_init(vec!["alice", "bob", "carl"])
     } else {
-        Default::default()
-    };
-
-    state.prompts = {
-        let s: String = std::fs::read_to_string("assets/prompts.txt").unwrap_or_default();
-        let mut lines = s
-            .lines()
-            .filter_map(|line| {
-                let fragments = line.split("___").collect::<Vec<_>>();
-                if fragments.len() == 2 {
-                    Some((fragments[0].to_owned(), fragments[1].to_owned()))
-                } else {
-                    log!("Discarding invalid prompt: {}", line);
-                    None
-                }
-            })
-            .collect::<Vec<_>>();
-        shuffle(&mut lines);
-        lines
+        GameState::default()
     };
-
+    state.reseed(initial_seed());
     state
 }
 
+/// Splits a prompt template into the text before & after the acronym.
+/// Valid templates have exactly one blank, written as "___".
+pub fn parse_prompt(template: &str) -> Option<(String, String)> {
+    let fragments = template.split("___").collect::<Vec<_>>();
+    if fragments.len() == 2 {
+        Some((fragments[0].to_owned(), fragments[1].to_owned()))
+    } else {
+        None
+    }
+}
+
 pub fn demo_init(players: Vec<&str>) -> GameState {
     let players = players
         .into_iter()
@@ -301,6 +694,9 @@ pub fn demo_init(players: Vec<&str>) -> GameState {
             id: name.to_owned(),
             name: name.to_owned(),
             quit: false,
+            connected: false,
+            offline_since: None,
+            bot: false,
         })
         .collect::<Vec<_>>();
 
@@ -312,12 +708,19 @@ pub fn demo_init(players: Vec<&str>) -> GameState {
     GameState {
         rounds: Vec::with_capacity(2 * &players.len()),
         players,
+        host: rotation.first().cloned(),
         rotation,
+        audience: Vec::new(),
         step: GameStep::Setup,
         timer: Timer::default(),
+        timer_generation: 0,
         shuffled_submissions: Vec::new(),
         config: Config::default(),
+        version: 0,
         prompts: Vec::new(),
+        player_prompts: Vec::new(),
+        seed: 0,
+        rng: GameRng::default(),
     }
 }
 
@@ -393,4 +796,322 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn game_over() {
+        let mut state = demo_init(vec!["a", "b", "c"]);
+        state.config.game_length = GameLength::RoundsPerJudge(1);
+        state.rounds = vec![Round {
+            winner: Some("a".to_owned()),
+            prompt: Prompt {
+                acronym: "abc".to_owned(),
+                ..Prompt::default()
+            },
+            submissions: vec![(
+                "a".to_owned(),
+                vec!["A".to_owned(), "B".to_owned(), "C".to_owned()],
+            )]
+            .into_iter()
+            .collect::<HashMap<_, _>>(),
+            ..Round::default()
+        }];
+        assert!(!state.is_game_over());
+
+        state.config.target_score = Some(3);
+        assert!(state.is_game_over());
+
+        state.config.target_score = None;
+        state.config.game_length = GameLength::Rounds(1);
+        assert!(state.is_game_over());
+    }
+
+    #[test]
+    fn quitters_dont_count_towards_the_game_length() {
+        let mut state = demo_init(vec!["a", "b", "c"]);
+        state.config.game_length = GameLength::RoundsPerJudge(2);
+        assert_eq!(state.total_rounds(), 6);
+
+        state.players.get_mut("c").unwrap().quit = true;
+        assert_eq!(state.total_rounds(), 4);
+    }
+
+    #[test]
+    fn head_to_head_scores() {
+        let mut state = demo_init(vec!["a", "b", "c"]);
+        state.config.mode = GameMode::HeadToHead;
+        state.rounds = vec![Round {
+            // the judge isn't penalized for a lack of winner
+            winner: None,
+            prompt: Prompt {
+                acronym: "ab".to_owned(),
+                ..Prompt::default()
+            },
+            submissions: vec![
+                ("a".to_owned(), vec!["A".to_owned(), "B".to_owned()]),
+                ("b".to_owned(), vec!["A!".to_owned(), "B".to_owned()]),
+                ("c".to_owned(), vec!["A".to_owned(), "B".to_owned()]),
+            ]
+            .into_iter()
+            .collect::<HashMap<_, _>>(),
+            votes: vec![
+                ("a".to_owned(), "b".to_owned()),
+                ("b".to_owned(), "a".to_owned()),
+                ("c".to_owned(), "b".to_owned()),
+            ]
+            .into_iter()
+            .collect::<HashMap<_, _>>(),
+            ..Round::default()
+        }];
+        assert_eq!(
+            state.scores(),
+            vec![
+                ("a".to_owned(), 2),
+                ("b".to_owned(), 2),
+                ("c".to_owned(), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn player_prompts_are_dealt_into_played_rounds() {
+        let mut state = demo_init(vec!["a", "b", "c"]);
+        state.config.game_length = GameLength::Rounds(2);
+        state.prompts = (0..10)
+            .map(|i| PromptTemplate {
+                before: format!("deck {i} "),
+                after: String::new(),
+                letters: None,
+            })
+            .collect();
+        state.player_prompts = vec![PlayerPrompt {
+            id: "1".to_owned(),
+            author: "a".to_owned(),
+            before: "player ".to_owned(),
+            after: String::new(),
+        }];
+
+        state.deal_player_prompts();
+
+        assert_eq!(state.prompts.len(), 11);
+        assert!(state.prompts[..2].iter().any(|p| p.before == "player "));
+    }
+
+    #[test]
+    fn optional_scoring_rules() {
+        let round = |winner: Option<&str>, judge, acronym: &str, submission: &str| Round {
+            winner: winner.map(|w| w.to_owned()),
+            judge,
+            prompt: Prompt {
+                acronym: acronym.to_owned(),
+                ..Prompt::default()
+            },
+            submissions: winner
+                .map(|w| (w.to_owned(), vec![submission.to_owned()]))
+                .into_iter()
+                .collect::<HashMap<_, _>>(),
+            ..Round::default()
+        };
+        let mut state = GameState {
+            rounds: vec![
+                round(Some("a"), 0, "abc", "A-B-C"),
+                round(None, 1, "de", ""),
+                round(Some("a"), 2, "fg", "FG"),
+            ],
+            ..demo_init(vec!["a", "b", "c"])
+        };
+        state.config.game_length = GameLength::Rounds(3);
+
+        state.config.scoring = ScoringRules {
+            character_penalty: true,
+            judge_timeout_penalty: true,
+            double_points_second_half: false,
+            audience_bonus: 0,
+        };
+        assert_eq!(
+            state.scores(),
+            vec![
+                ("a".to_owned(), 3),
+                ("c".to_owned(), 0),
+                ("b".to_owned(), -2)
+            ]
+        );
+
+        state.config.scoring = ScoringRules {
+            character_penalty: false,
+            judge_timeout_penalty: false,
+            double_points_second_half: true,
+            audience_bonus: 0,
+        };
+        // only the last round is in the second half
+        assert_eq!(
+            state.scores(),
+            vec![
+                ("a".to_owned(), 7),
+                ("b".to_owned(), 0),
+                ("c".to_owned(), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn authors_are_hidden_until_the_winner_is_picked() {
+        let mut state = demo_init(vec!["a", "b", "c"]);
+        state.step = GameStep::Judging;
+        let mut round = Round::default();
+        for id in ["a", "b"] {
+            round.token(&id.to_owned());
+            round
+                .submissions
+                .insert(id.to_owned(), vec![id.to_uppercase()]);
+        }
+        state.rounds = vec![round];
+        state.shuffle_current_round_submissions();
+
+        let client = state.to_client_state(Some(&"a".to_owned()), Instant::now());
+        let own = client.own_submission.clone().unwrap();
+        assert!(client.authors.is_empty());
+        assert_eq!(client.submissions.len(), 2);
+        assert!(client
+            .submissions
+            .iter()
+            .all(|(token, _)| token != "a" && token != "b"));
+        assert_eq!(state.rounds[0].author_of(&own), Some(&"a".to_owned()));
+
+        state.rounds[0].winner = Some("b".to_owned());
+        let client = state.to_client_state(None, Instant::now());
+        assert!(client.own_submission.is_none());
+        assert_eq!(client.authors.len(), 2);
+        assert!(client.authors.contains(&(own, "a".to_owned())));
+    }
+
+    #[test]
+    fn names_are_unique() {
+        let mut state = demo_init(vec!["alice", "bob"]);
+        state.audience.push(Player {
+            id: "carl".to_owned(),
+            name: "Carl".to_owned(),
+        });
+
+        assert!(state.is_name_taken(&"dan".to_owned(), " Alice"));
+        assert!(state.is_name_taken(&"dan".to_owned(), "carl"));
+        // keeping your own name isn't a clash
+        assert!(!state.is_name_taken(&"bob".to_owned(), "bob"));
+        assert!(!state.is_name_taken(&"dan".to_owned(), "dan"));
+    }
+
+    #[test]
+    fn host_hands_off_to_the_next_connected_player() {
+        let mut state = demo_init(vec!["a", "b", "c", "d"]);
+        state.host = Some("b".to_owned());
+        state.players.get_mut("c").unwrap().quit = true;
+        let connect = |state: &mut GameState, ids: &[&str]| {
+            for p in state.players.values_mut() {
+                p.connected = ids.contains(&p.id.as_str());
+            }
+        };
+
+        connect(&mut state, &["a", "b", "c", "d"]);
+        assert_eq!(state.next_host(), Some("d".to_owned()));
+        connect(&mut state, &["a"]);
+        assert_eq!(state.next_host(), Some("a".to_owned()));
+        connect(&mut state, &["b"]);
+        assert_eq!(state.next_host(), None);
+    }
+
+    #[test]
+    fn config_validation() {
+        assert_eq!(Config::default().validate(), Ok(()));
+
+        let config = Config {
+            letters_per_acronym: Range { min: 5, max: 3 },
+            ..Config::default()
+        };
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.letters_per_acronym.max = Config::MAX_LETTERS + 1;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.timer_secs.judging = TimerSecs::MAX + 1;
+        assert!(config.validate().is_err());
+
+        let config = Config {
+            game_length: GameLength::Rounds(0),
+            ..Config::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn nobody_waits_on_players_who_quit() {
+        let mut state = demo_init(vec!["a", "b", "c"]);
+        state.rounds = vec![Round::default()];
+        let round = &mut state.rounds[0];
+        round
+            .submissions
+            .insert("b".to_owned(), vec!["B".to_owned()]);
+        assert!(!state.all_submitted());
+        assert_eq!(state.acting_judge(), Some("a".to_owned()));
+
+        state.players.get_mut("c").unwrap().quit = true;
+        assert!(state.all_submitted());
+
+        // the host fills in for a judge who left
+        state.players.get_mut("a").unwrap().quit = true;
+        state.host = Some("b".to_owned());
+        assert_eq!(state.acting_judge(), Some("b".to_owned()));
+    }
+
+    #[test]
+    fn nobody_waits_on_players_with_nothing_to_vote_for() {
+        let mut state = demo_init(vec!["a", "b", "c"]);
+        state.rounds = vec![Round::default()];
+        let round = &mut state.rounds[0];
+        round
+            .submissions
+            .insert("b".to_owned(), vec!["B".to_owned()]);
+        round.votes.insert("a".to_owned(), "b".to_owned());
+        assert!(!state.all_voted());
+
+        // b's own submission is the only one
+        state.rounds[0].votes.insert("c".to_owned(), "b".to_owned());
+        assert!(state.all_voted());
+    }
+
+    #[test]
+    fn audience_bonus() {
+        let mut state = demo_init(vec!["a", "b", "c"]);
+        state.config.scoring.audience_bonus = 3;
+        state.rounds = vec![Round {
+            winner: Some("a".to_owned()),
+            prompt: Prompt {
+                acronym: "ab".to_owned(),
+                ..Prompt::default()
+            },
+            submissions: vec![
+                ("a".to_owned(), vec!["A".to_owned(), "B".to_owned()]),
+                ("b".to_owned(), vec!["A".to_owned(), "B".to_owned()]),
+            ]
+            .into_iter()
+            .collect::<HashMap<_, _>>(),
+            audience_votes: vec![
+                ("x".to_owned(), "b".to_owned()),
+                ("y".to_owned(), "b".to_owned()),
+                ("z".to_owned(), "a".to_owned()),
+            ]
+            .into_iter()
+            .collect::<HashMap<_, _>>(),
+            ..Round::default()
+        }];
+
+        assert_eq!(
+            state.scores(),
+            vec![
+                ("b".to_owned(), 3),
+                ("a".to_owned(), 2),
+                ("c".to_owned(), 0)
+            ]
+        );
+    }
 }
@@ -1,6 +1,8 @@
 use crate::types::{PlayerId, SessionId};
 use std::collections::HashMap;
 
+/// Which player each session belongs to.
+/// The sockets themselves are owned by the room's driver.
 #[cfg(not(feature = "impersonation"))]
 #[derive(Debug, Default)]
 pub struct Sessions {
@@ -39,6 +41,10 @@ impl Sessions {
     pub fn player_id(&self, session_id: &SessionId) -> Option<&PlayerId> {
         self.player_ids.get(session_id)
     }
+
+    pub fn is_connected(&self, player_id: &PlayerId) -> bool {
+        self.session_ids.contains_key(player_id)
+    }
 }
 
 /// Compatibility layer around a single hashmap
@@ -69,4 +75,8 @@ impl Sessions {
     pub fn player_id(&self, session_id: &SessionId) -> Option<&PlayerId> {
         self.player_ids.get(session_id)
     }
+
+    pub fn is_connected(&self, player_id: &PlayerId) -> bool {
+        self.player_ids.values().any(|id| id == player_id)
+    }
 }
@@ -1,10 +1,11 @@
-use super::sync::*;
+use super::sync::{room, Mail, Room};
 use crate::extensions::ResultExt;
 use crate::types::*;
 use ::actix_web::{rt, web, Error, HttpRequest, HttpResponse};
 use ::actix_ws::{CloseCode, CloseReason, Message};
 use ::futures::StreamExt as _;
 use ::leptos::logging::log;
+use ::std::sync::Arc;
 use ::std::time::{Duration, Instant};
 use ::tokio::{
     pin, select,
@@ -20,29 +21,45 @@ const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
 /// How long before lack of client response causes a timeout.
 const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// How many private messages can queue up for a socket, before they get dropped.
+const OUTBOX_CAPACITY: usize = 100;
+
 /// Handshake and start websocket handler with heartbeats.
 /// Adapted from [Actix example code](https://github.com/actix/examples/blob/25368e6b65120224f845137c9333850968456153/websockets/echo-actorless/src/handler.rs).
+/// Responds with a 404, if the room code doesn't match an active room.
 pub async fn handle_ws_request(
     req: HttpRequest,
     stream: web::Payload,
+    code: web::Path<RoomCode>,
 ) -> Result<HttpResponse, Error> {
+    let Some(room) = room(&code) else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
     let (res, session, msg_stream) = actix_ws::handle(&req, stream)?;
-    rt::spawn(handle_connection(session, msg_stream));
+    rt::spawn(handle_connection(session, msg_stream, room));
     Ok(res)
 }
 
 async fn handle_connection(
     mut session: actix_ws::Session,
     mut msg_stream: actix_ws::MessageStream,
+    room: Arc<Room>,
 ) {
-    log!("connected");
+    log!("connected to room {}", room.code);
 
-    let mut server_broadcast = subscribe();
-    let mailer = mailer();
+    let mut server_broadcast = room.subscribe();
+    let mailer = room.mailer();
+    // the socket shouldn't keep the room alive after it's been garbage collected
+    drop(room);
     let mut last_heartbeat = Instant::now();
     let mut interval = interval(HEARTBEAT_INTERVAL);
 
     let session_id = SessionId(Uuid::new_v4().to_string());
+    let (outbox, mut inbox) = mpsc::channel(OUTBOX_CAPACITY);
+    mailer
+        .send(Mail::Open(session_id.clone(), outbox))
+        .await
+        .ok_or_log();
     let reason = loop {
         let tick = interval.tick();
         pin!(tick);
@@ -59,15 +76,21 @@ async fn handle_connection(
             // The heartbeat is the least important, so it can wait until
             // no other activity is going on.
 
-            // (1) Server broadcast
+            // (1) Private messages, for this session only.
+            // Ahead of broadcasts, so a client gets its game state before the patches on top of it.
+            // Disabled once the room closes, which the broadcast takes care of.
+            Some(msg) = inbox.recv() =>
+                handle_server_message(msg, &mut session).await,
+
+            // (2) Server broadcast
             msg = server_broadcast.recv() =>
-                handle_server_message(msg, &mut session, &session_id).await,
+                handle_broadcast(msg, &mut session).await,
 
-            // (2) Client websocket
+            // (3) Client websocket
             msg = msg_stream.next() =>
                 handle_client_message(msg, &mut session, &session_id, &mut last_heartbeat, &mailer).await,
 
-            // (3) Heartbeat. Sends a ping, or closes the socket.
+            // (4) Heartbeat. Sends a ping, or closes the socket.
             _ = tick =>
                 handle_heartbeat(&mut session, last_heartbeat).await,
         };
@@ -79,7 +102,7 @@ async fn handle_connection(
 
     session.close(Some(reason)).await.ok_or_log();
     mailer
-        .send((session_id, ClientMessage::Disconnect))
+        .send(Mail::Client(session_id, ClientMessage::Disconnect))
         .await
         .ok_or_log();
 
@@ -109,7 +132,7 @@ async fn handle_client_message(
     session: &mut actix_ws::Session,
     session_id: &SessionId,
     last_heartbeat: &mut Instant,
-    mailer: &mpsc::Sender<(SessionId, ClientMessage)>,
+    mailer: &mpsc::Sender<Mail>,
 ) -> Option<CloseReason> {
     // websocket closed
     if msg.is_none() {
@@ -122,7 +145,10 @@ async fn handle_client_message(
         match msg {
             Message::Text(text) => {
                 if let Some(msg) = serde_json::from_str(&text).ok_or_log() {
-                    mailer.send((session_id.clone(), msg)).await.ok_or_log();
+                    mailer
+                        .send(Mail::Client(session_id.clone(), msg))
+                        .await
+                        .ok_or_log();
                 }
             }
 
@@ -155,28 +181,42 @@ async fn handle_client_message(
     None
 }
 
-async fn handle_server_message(
+async fn handle_broadcast(
     msg: Result<ServerMessage, RecvError>,
     session: &mut actix_ws::Session,
-    session_id: &SessionId,
 ) -> Option<CloseReason> {
+    // the room was garbage collected
+    if let Err(RecvError::Closed) = msg {
+        return Some(CloseReason {
+            code: CloseCode::Away,
+            description: Some("room closed".to_owned()),
+        });
+    }
+
     if let Some(msg) = msg.ok_or_log() {
-        let serialized = serde_json::to_string(&msg).ok_or_log();
-
-        if let ServerMessage::DuplicateSession(id) = msg {
-            if id == *session_id {
-                return Some(CloseReason {
-                    code: CloseCode::Other(0),
-                    description: Some(
-                        "player cannot open duplicate web socket connections".to_owned(),
-                    ),
-                });
-            }
-        }
+        return handle_server_message(msg, session).await;
+    }
+    None
+}
 
-        if let Some(text) = serialized {
-            session.text(text).await.ok_or_log();
-        }
+async fn handle_server_message(
+    msg: ServerMessage,
+    session: &mut actix_ws::Session,
+) -> Option<CloseReason> {
+    if let Some(text) = serde_json::to_string(&msg).ok_or_log() {
+        session.text(text).await.ok_or_log();
+    }
+
+    // the client is told why, before the socket is closed
+    if let ServerMessage::Error {
+        code: ErrorCode::DuplicateSession,
+        ..
+    } = msg
+    {
+        return Some(CloseReason {
+            code: CloseCode::Other(0),
+            description: Some("player cannot open duplicate web socket connections".to_owned()),
+        });
     }
     None
 }
@@ -1,3 +1,6 @@
+use crate::constants::DEV_MODE;
+pub use crate::letter_distribution::LetterDistribution;
+pub use crate::validation::{validate_submission, validate_word, AlphabetPolicy};
 use serde::{Deserialize, Serialize};
 
 /// User submitted pick
@@ -8,6 +11,22 @@ pub type PlayerId = String;
 
 pub type PlayerName = String;
 
+/// Short code generated server side when a room is created.
+/// Shows up in the room url, e.g. /room/QXJB
+pub type RoomCode = String;
+
+/// Uuid generated automatically server side
+/// Identifies a prompt written by a player
+pub type PromptId = String;
+
+/// Uuid generated automatically server side, when a player submits.
+/// Stands in for the author while submissions are judged, so the author stays anonymous.
+/// Only valid for the current round.
+pub type SubmissionToken = String;
+
+/// File name of a prompt pack, without the extension, e.g. "classic"
+pub type PackId = String;
+
 /// Uuid generated automatically server side
 /// Uniquely identifies each web socket connection
 #[derive(Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq)]
@@ -19,6 +38,7 @@ pub enum GameStep {
     Setup, // Player's joining and game config
     Submission, // Player's submit acronyms
     Judging,    // Judge judges
+    Voting,     // Everyone votes, in head to head mode
     Results,    // Scoreboard at game end
 }
 
@@ -28,47 +48,263 @@ pub struct Player {
     pub name: String,
 }
 
+/// A prompt template written by a player at game setup.
+/// The acronym goes in between the before & after text.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlayerPrompt {
+    pub id: PromptId,
+    pub author: PlayerId,
+    pub before: String,
+    pub after: String,
+}
+
+/// Prompt pack that's selected when a game is created
+pub const DEFAULT_PROMPT_PACK: &str = "classic";
+
+/// Who a prompt pack is appropriate for
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum ContentRating {
+    #[default]
+    Family,
+    Teen,
+    Mature,
+}
+
+impl ContentRating {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ContentRating::Family => "Family",
+            ContentRating::Teen => "Teen",
+            ContentRating::Mature => "Mature",
+        }
+    }
+}
+
+/// Everything about a prompt pack, except the prompts themselves.
+/// Shown to the host when picking packs at game setup.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PromptPackInfo {
+    pub id: PackId,
+    pub name: String,
+    pub description: String,
+    pub rating: ContentRating,
+    pub prompt_count: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
 pub struct Config {
     pub letters_per_acronym: Range<usize>,
+    pub letter_distribution: LetterDistribution,
+    pub acronym_style: AcronymStyle,
+    /// Characters allowed in submissions
+    pub alphabet: AlphabetPolicy,
+    /// Prompts are drawn from all of these packs
+    pub prompt_packs: Vec<PackId>,
+    pub mode: GameMode,
+    pub timer_secs: TimerSecs,
+    pub game_length: GameLength,
+    pub scoring: ScoringRules,
+    /// The game ends early once someone reaches this score
+    pub target_score: Option<i64>,
+}
+
+/// Optional scoring rules, on top of the base rule: a winning submission earns a point per letter
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct ScoringRules {
+    /// Lose a point for each character of a submission that isn't a letter
+    pub character_penalty: bool,
+    /// The judge loses the round's points if they don't pick a winner in time
+    pub judge_timeout_penalty: bool,
+    /// Every round in the second half of the game is worth twice as much
+    pub double_points_second_half: bool,
+    /// Bonus points for the audience's favorite submission each round. Zero to disable.
+    pub audience_bonus: i64,
+}
+
+impl Default for ScoringRules {
+    fn default() -> Self {
+        ScoringRules {
+            character_penalty: true,
+            judge_timeout_penalty: true,
+            double_points_second_half: false,
+            audience_bonus: 2,
+        }
+    }
+}
+
+impl ScoringRules {
+    /// Plain english description of each active rule, for showing to players
+    pub fn descriptions(&self, mode: GameMode) -> Vec<String> {
+        let mut rules = vec![match mode {
+            GameMode::Judge => "The judge's pick earns a point per letter in the acronym.",
+            GameMode::HeadToHead => "Every vote earns a point per letter in the acronym.",
+        }
+        .to_owned()];
+        if self.character_penalty {
+            rules.push("Lose a point for each character that isn't a letter.".to_owned());
+        }
+        if self.judge_timeout_penalty && mode == GameMode::Judge {
+            rules.push(
+                "The judge loses those points if they don't pick a winner in time.".to_owned(),
+            );
+        }
+        if self.audience_bonus > 0 {
+            rules.push(format!(
+                "The audience's favorite earns {} bonus points.",
+                self.audience_bonus
+            ));
+        }
+        if self.double_points_second_half {
+            rules.push("Points are doubled in the second half of the game.".to_owned());
+        }
+        rules
+    }
+}
+
+/// How many rounds to play, if nobody reaches the target score first
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameLength {
+    /// Everyone gets this many turns as judge
+    RoundsPerJudge(usize),
+    /// A fixed number of rounds, regardless of player count
+    Rounds(usize),
+}
+
+impl GameLength {
+    pub fn total_rounds(&self, player_count: usize) -> usize {
+        match self {
+            GameLength::RoundsPerJudge(n) => n.saturating_mul(player_count),
+            GameLength::Rounds(n) => *n,
+        }
+    }
+}
+
+impl Default for GameLength {
+    fn default() -> Self {
+        if DEV_MODE {
+            GameLength::Rounds(3)
+        } else {
+            GameLength::RoundsPerJudge(2)
+        }
+    }
+}
+
+/// How long each timer runs for, in seconds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct TimerSecs {
+    pub submission: u64,
+    /// Also used for voting, in head to head mode
+    pub judging: u64,
+    pub round_winner: u64,
+    /// How long a disconnected player has to come back, before they quit the game
+    pub reconnect_grace: u64,
+}
+
+impl TimerSecs {
+    /// Shortest allowed duration for any timer
+    pub const MIN: u64 = 5;
+    /// Longest allowed duration for any timer
+    pub const MAX: u64 = 600;
+}
+
+impl Default for TimerSecs {
+    fn default() -> Self {
+        TimerSecs {
+            submission: 60,
+            judging: 45,
+            round_winner: 10,
+            reconnect_grace: 30,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum GameMode {
+    /// Each round one player is the judge, and picks their favorite submission (Apples to Apples)
+    #[default]
+    Judge,
+    /// Nobody judges, everyone submits and then votes for their favorite submission (Quiplash)
+    HeadToHead,
+}
+
+/// How the letters of each prompt are picked
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum AcronymStyle {
+    /// Any letters, in any order (e.g. "XKQ")
+    #[default]
+    Initialism,
+    /// Letters that can be read out loud as a word (e.g. "BLAST")
+    Pronounceable,
 }
 
 /// game state for a single client
 /// some of the server game state should be hidden, and some should be transformed for easier consumption
 #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct ClientGameState {
+    /// Number of patches applied since the game started.
+    /// A patch whose version doesn't follow on from this one means the client missed an update.
+    pub version: u64,
     pub judge: Option<PlayerId>,
+    /// Runs the game, e.g. starts it & kicks players
+    pub host: Option<PlayerId>,
+    /// Players who are still in the game, but disconnected.
+    /// They have until the reconnect grace period is over to come back.
+    pub offline: Vec<PlayerId>,
+    /// Players run by the server, added by the host to fill out small games
+    pub bots: Vec<PlayerId>,
     pub step: GameStep,
     pub players: Vec<Player>,
     pub prompt: Prompt,
     pub timer: Option<u64>,
     /// everyone can see the current submission count
     pub submission_count: usize,
+    /// everyone can see the current vote count, in head to head mode
+    pub vote_count: usize,
+    /// Spectators, who can't submit but can vote for their favorite submission
+    pub audience: Vec<Player>,
+    pub audience_vote_count: usize,
     /// Empty vector when not at the judging step.
-    /// This technically enables cheating,
-    /// if a savvy player were to inspect the network tab &
-    /// cross reference with the players vector.
-    pub submissions: Vec<(PlayerId, Submission)>,
+    /// Submissions are anonymous, until the authors are revealed along with the round winner.
+    pub submissions: Vec<(SubmissionToken, Submission)>,
+    /// Who wrote each submission.
+    /// Empty until the round winner is revealed.
+    pub authors: Vec<(SubmissionToken, PlayerId)>,
+    /// Your own submission this round, so you can recognize it while it's anonymous
+    pub own_submission: Option<SubmissionToken>,
     /// Empty until the results step.
     pub scores: Vec<(PlayerName, i64)>,
+    /// How much of each player's score came from audience votes.
+    /// Empty until the results step.
+    pub audience_bonuses: Vec<(PlayerName, i64)>,
     pub round_winner: Option<PlayerId>,
+    /// Votes received per submission.
+    /// Empty until voting ends, in head to head mode.
+    pub vote_tallies: Vec<(SubmissionToken, usize)>,
     pub round_counter: String,
     pub config: Config,
+    /// Prompts written by players.
+    /// Empty after setup, so they're still a surprise during the game.
+    pub player_prompts: Vec<PlayerPrompt>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum TimerTag {
     Submission,
     Judging,
+    Voting,
     ShowRoundWinner,
 }
 
 impl TimerTag {
-    pub fn secs(&self) -> u64 {
+    pub fn secs(&self, config: &Config) -> u64 {
+        let secs = &config.timer_secs;
         match self {
-            TimerTag::Submission => 60,
-            TimerTag::Judging => 45,
-            TimerTag::ShowRoundWinner => 10,
+            TimerTag::Submission => secs.submission,
+            TimerTag::Judging | TimerTag::Voting => secs.judging,
+            TimerTag::ShowRoundWinner => secs.round_winner,
         }
     }
 }
@@ -88,36 +324,179 @@ pub enum ClientMessage {
     JoinGame {
         name: String,
     },
+    /// Watch the game as a spectator, at any step
+    JoinAudience {
+        name: String,
+    },
     KickPlayer(PlayerId),
-    StartGame(Config),
+    /// Hand the host role to another player
+    TransferHost(PlayerId),
+    /// Add a player run by the server during setup. Bots leave the same way as anyone, with `KickPlayer`.
+    AddBot,
+    /// Sent by the host as they edit the config during setup
+    UpdateConfig(Config),
+    /// Start with the config from the last `UpdateConfig`
+    StartGame,
+    /// Add a prompt template during setup, e.g. "In case of emergency, remember ___"
+    SubmitPrompt(String),
+    /// Remove a player's prompt during setup
+    RemovePrompt(PromptId),
     SubmitAcronym(Submission),
-    JudgeRound(PlayerId),
+    JudgeRound(SubmissionToken),
+    /// Vote for another player's submission, in head to head mode
+    CastVote(SubmissionToken),
+    /// Spectators vote for their favorite submission, while the round is being judged
+    AudienceVote(SubmissionToken),
     GetRemainingTime,
     /// for debugging only
     ResetState,
     /// for debugging only
     StopTimer,
+    /// Ask for the full game state, after missing a patch
+    Resync,
+}
+
+impl ClientMessage {
+    /// Name of the variant, so error replies can say which message they are about
+    pub fn name(&self) -> &'static str {
+        match self {
+            ClientMessage::Connect(_) => "Connect",
+            ClientMessage::Disconnect => "Disconnect",
+            ClientMessage::JoinGame { .. } => "JoinGame",
+            ClientMessage::JoinAudience { .. } => "JoinAudience",
+            ClientMessage::KickPlayer(_) => "KickPlayer",
+            ClientMessage::TransferHost(_) => "TransferHost",
+            ClientMessage::AddBot => "AddBot",
+            ClientMessage::UpdateConfig(_) => "UpdateConfig",
+            ClientMessage::StartGame => "StartGame",
+            ClientMessage::SubmitPrompt(_) => "SubmitPrompt",
+            ClientMessage::RemovePrompt(_) => "RemovePrompt",
+            ClientMessage::SubmitAcronym(_) => "SubmitAcronym",
+            ClientMessage::JudgeRound(_) => "JudgeRound",
+            ClientMessage::CastVote(_) => "CastVote",
+            ClientMessage::AudienceVote(_) => "AudienceVote",
+            ClientMessage::GetRemainingTime => "GetRemainingTime",
+            ClientMessage::ResetState => "ResetState",
+            ClientMessage::StopTimer => "StopTimer",
+            ClientMessage::Resync => "Resync",
+        }
+    }
+}
+
+/// Why the server rejected a message.
+/// Codes are stable, so clients can match on them. The error message is only for display.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The message doesn't make sense at the current game step
+    WrongStep,
+    /// Only players who joined the game can do that
+    NotJoined,
+    /// Only the host can do that
+    NotHost,
+    /// Only the judge can do that
+    NotJudge,
+    /// Only the audience can do that
+    NotAudience,
+    /// The judge sits out submitting, so they can't pick their own submission
+    IsJudge,
+    /// Not allowed for some other reason, like removing someone else's prompt
+    NotAllowed,
+    /// Someone else in the room already has that name
+    NameTaken,
+    /// The submission, or the submission you picked, isn't valid
+    InvalidSubmission,
+    /// Prompt templates need exactly one blank, and can't be too long.
+    /// Also sent when removing a prompt that doesn't exist.
+    InvalidPrompt,
+    /// The config isn't playable, e.g. the acronym length range is backwards
+    InvalidConfig,
+    /// No more players can join the game
+    RoomFull,
+    /// The player is already connected from somewhere else, e.g. another browser tab.
+    /// The socket is closed right after.
+    DuplicateSession,
 }
 
 /// message from the server broadcast to each client
 #[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ServerMessage {
-    /// Sent when a client connects for the first time.
-    /// Theoretically, a client connecting is the only time when we need to send this message.
-    /// We could send smaller message payloads for other events and patch game state on the client
-    /// side.
-    /// That is the plan longer term, but for development speed, for now we're saving time by sending the
-    /// whole client game state.
-    /// I have implemented the more granular approach for the PlayerJoined payload to demonstrate
-    /// its possible.
+    /// The whole client game state.
+    /// Only sent when a client connects, or asks to resync after missing a patch.
     GameState(ClientGameState),
+    /// An incremental update to the client game state.
+    /// Each patch bumps the version by one, so clients can tell if they missed one.
+    Patch { version: u64, patch: StatePatch },
+    /// Seconds remaining on the clock, sent only to the client that asked
+    UpdateRemainingTime(Option<u64>),
+    /// Sent privately, when the server rejects one of your messages
+    Error {
+        code: ErrorCode,
+        message: String,
+        /// Name of the client message that was rejected
+        in_reply_to: Option<String>,
+    },
+    /// Sent privately, with information meant only for you
+    Hint(String),
+    /// Sent privately when your submission is accepted
+    OwnSubmission(SubmissionToken),
+}
+
+/// A granular change to the client game state
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum StatePatch {
+    /// Joined the game, or changed their name
     PlayerJoined(Player),
-    ShowRoundWinner(PlayerId),
+    /// The host added a bot
+    BotJoined(Player),
+    PlayerKicked(PlayerId),
+    /// A player's socket closed, or they came back
+    ConnectionChanged {
+        id: PlayerId,
+        connected: bool,
+    },
+    HostChanged(PlayerId),
+    /// Joined the audience, or changed their name
+    AudienceJoined(Player),
+    PromptAdded(PlayerPrompt),
+    PromptRemoved(PromptId),
+    /// The host changed the config during setup. Sent for every accepted edit.
+    ConfigChanged(Config),
+    StepChanged {
+        step: GameStep,
+        timer: Option<u64>,
+    },
+    /// A new round begins at the submission step, and everything from the last round is cleared
+    RoundStarted {
+        judge: Option<PlayerId>,
+        prompt: Prompt,
+        round_counter: String,
+        timer: Option<u64>,
+    },
     IncrementSubmissionCount,
-    /// Seconds remaining on the clock
-    UpdateRemainingTime(Option<u64>),
-    DuplicateSession(SessionId),
+    /// Someone took over judging after they submitted, so their submission was dropped
+    SubmissionWithdrawn,
+    /// Submissions are shown in shuffled order, once it's time to judge or vote on them
+    SubmissionsRevealed(Vec<(SubmissionToken, Submission)>),
+    /// The judge's pick, along with who wrote each submission
+    ShowRoundWinner {
+        winner: PlayerId,
+        authors: Vec<(SubmissionToken, PlayerId)>,
+    },
+    IncrementVoteCount,
+    IncrementAudienceVoteCount,
+    /// End of voting, in head to head mode
+    VotesRevealed {
+        winner: Option<PlayerId>,
+        tallies: Vec<(SubmissionToken, usize)>,
+        authors: Vec<(SubmissionToken, PlayerId)>,
+    },
+    /// End of the game
+    ScoresPublished {
+        scores: Vec<(PlayerName, i64)>,
+        audience_bonuses: Vec<(PlayerName, i64)>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -126,10 +505,101 @@ pub struct Range<T> {
     pub max: T,
 }
 
+impl Config {
+    /// Fewest letters an acronym can have
+    pub const MIN_LETTERS: usize = 2;
+    /// Most letters an acronym can have
+    pub const MAX_LETTERS: usize = 10;
+    /// Most rounds in total, or per judge
+    pub const MAX_ROUNDS: usize = 100;
+    /// Highest target score, and highest audience bonus
+    pub const MAX_POINTS: i64 = 1000;
+
+    /// Checks the config makes for a playable game.
+    /// The error is a reason that can be shown to the host.
+    pub fn validate(&self) -> Result<(), String> {
+        let letters = &self.letters_per_acronym;
+        if letters.min < Self::MIN_LETTERS || letters.max > Self::MAX_LETTERS {
+            return Err(format!(
+                "Acronyms need between {} and {} letters",
+                Self::MIN_LETTERS,
+                Self::MAX_LETTERS
+            ));
+        }
+        if letters.min > letters.max {
+            return Err("The shortest acronym can't be longer than the longest".to_owned());
+        }
+
+        let timers = &self.timer_secs;
+        let in_bounds = |secs| (TimerSecs::MIN..=TimerSecs::MAX).contains(&secs);
+        if ![
+            timers.submission,
+            timers.judging,
+            timers.round_winner,
+            timers.reconnect_grace,
+        ]
+        .into_iter()
+        .all(in_bounds)
+        {
+            return Err(format!(
+                "Timers need between {} and {} seconds",
+                TimerSecs::MIN,
+                TimerSecs::MAX
+            ));
+        }
+
+        if let GameLength::RoundsPerJudge(0) | GameLength::Rounds(0) = self.game_length {
+            return Err("The game needs at least one round".to_owned());
+        }
+        if let GameLength::RoundsPerJudge(n) | GameLength::Rounds(n) = self.game_length
+            && n > Self::MAX_ROUNDS
+        {
+            return Err(format!(
+                "The game can't have more than {} rounds",
+                Self::MAX_ROUNDS
+            ));
+        }
+        if self.target_score.is_some_and(|t| t <= 0) {
+            return Err("The target score needs to be positive".to_owned());
+        }
+        if self.target_score.is_some_and(|t| t > Self::MAX_POINTS) {
+            return Err(format!(
+                "The target score can be at most {}",
+                Self::MAX_POINTS
+            ));
+        }
+        if self.scoring.audience_bonus < 0 {
+            return Err("The audience bonus can't be negative".to_owned());
+        }
+        if self.scoring.audience_bonus > Self::MAX_POINTS {
+            return Err(format!(
+                "The audience bonus can be at most {}",
+                Self::MAX_POINTS
+            ));
+        }
+
+        self.letter_distribution.validate()?;
+        // the server also checks the packs exist
+        if self.prompt_packs.is_empty() {
+            return Err("Pick at least one prompt pack".to_owned());
+        }
+        Ok(())
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config {
             letters_per_acronym: Range { min: 2, max: 6 },
+            letter_distribution: LetterDistribution::default(),
+            acronym_style: AcronymStyle::default(),
+            alphabet: AlphabetPolicy::default(),
+            prompt_packs: vec![DEFAULT_PROMPT_PACK.to_owned()],
+            mode: GameMode::default(),
+            timer_secs: TimerSecs::default(),
+            game_length: GameLength::default(),
+            scoring: ScoringRules::default(),
+            target_score: None,
         }
     }
 }
@@ -0,0 +1,124 @@
+use crate::constants::MAX_WORD_LENGTH;
+use crate::types::{Config, LetterDistribution, Submission};
+use serde::{Deserialize, Serialize};
+
+/// Which characters are allowed in a submission
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub enum AlphabetPolicy {
+    /// Any character, although the character penalty may still cost points
+    #[default]
+    Anything,
+    /// Letters, plus the apostrophes & hyphens that show up inside words
+    Words,
+    /// Nothing but letters
+    LettersOnly,
+}
+
+impl AlphabetPolicy {
+    pub fn allows(self, c: char, distribution: &LetterDistribution) -> bool {
+        match self {
+            AlphabetPolicy::Anything => true,
+            AlphabetPolicy::Words => is_letter(c, distribution) || matches!(c, '\'' | '-'),
+            AlphabetPolicy::LettersOnly => is_letter(c, distribution),
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            AlphabetPolicy::Anything => "Any characters",
+            AlphabetPolicy::Words => "Letters, apostrophes & hyphens",
+            AlphabetPolicy::LettersOnly => "Letters only",
+        }
+    }
+}
+
+/// A-Z in either case, or any letter in the game's alphabet (like Ñ)
+pub fn is_letter(c: char, distribution: &LetterDistribution) -> bool {
+    c.is_ascii_alphabetic() || distribution.contains(c)
+}
+
+/// Checks a single word of a submission, against the letter it should start with.
+/// The error is a reason that can be shown to the player.
+pub fn validate_word(lead: char, word: &str, config: &Config) -> Result<(), String> {
+    let starts_with_lead = word
+        .chars()
+        .next()
+        .is_some_and(|c| c.to_uppercase().eq(lead.to_uppercase()));
+    if !starts_with_lead {
+        return Err(format!(
+            "Should start with {}",
+            lead.to_uppercase().collect::<String>(),
+        ));
+    }
+
+    if word.chars().count() > MAX_WORD_LENGTH {
+        return Err(format!(
+            "Should be at most {} characters long",
+            MAX_WORD_LENGTH
+        ));
+    }
+
+    let policy = config.alphabet;
+    if let Some(c) = word
+        .chars()
+        .find(|c| !policy.allows(*c, &config.letter_distribution))
+    {
+        return Err(format!(
+            "Can't use {:?}. {} allowed.",
+            c,
+            policy.description()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks a whole submission, with one word per letter of the acronym
+pub fn validate_submission(
+    submission: &Submission,
+    acronym: &str,
+    config: &Config,
+) -> Result<(), String> {
+    let letters = acronym.chars().count();
+    if submission.len() != letters {
+        return Err(format!(
+            "Expected {} words, one per letter, but got {}",
+            letters,
+            submission.len()
+        ));
+    }
+
+    acronym
+        .chars()
+        .zip(submission)
+        .try_for_each(|(lead, word)| validate_word(lead, word, config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(s: &str) -> Submission {
+        s.split(' ').map(|w| w.to_owned()).collect()
+    }
+
+    #[test]
+    fn submissions_match_the_acronym() {
+        let mut config = Config::default();
+        assert_eq!(
+            validate_submission(&words("big red dog"), "BRD", &config),
+            Ok(())
+        );
+        assert!(validate_submission(&words("big red"), "BRD", &config).is_err());
+        assert!(validate_submission(&words("big red cat"), "BRD", &config).is_err());
+        assert!(validate_submission(&words("big red dog!"), "BRD", &config).is_ok());
+        assert!(validate_submission(&vec!["b".repeat(100)], "B", &config).is_err());
+
+        config.alphabet = AlphabetPolicy::Words;
+        assert!(validate_submission(&words("big red dog!"), "BRD", &config).is_err());
+        assert!(validate_submission(&words("big re-red dog's"), "BRD", &config).is_ok());
+
+        config.alphabet = AlphabetPolicy::LettersOnly;
+        assert!(validate_submission(&words("big re-red dog"), "BRD", &config).is_err());
+    }
+}